@@ -0,0 +1,382 @@
+//! A 2D point mass simulation of a gravity turn ascent to low orbit.
+//!
+//! The rocket flies straight up until it reaches `pitch_speed`, then holds
+//! `pitch_angle` from vertical until its velocity has turned to match, and
+//! follows its surface relative velocity from there on. Once above the
+//! atmosphere it burns prograde until the apoapsis reaches the target
+//! altitude, coasts towards apoapsis, and then burns to gain horizontal speed
+//! while holding its altitude until the periapsis reaches the target too.
+//!
+//! The engines run at their `max_throttle`. With `max_g_force` set they
+//! throttle down to hold the limit, as far as their `min_throttle` allows,
+//! which stretches their burn and adds to the gravity losses.
+//!
+//! Losses are measured against inertial velocity while the engines are
+//! running, so the delta-v expended is the change in speed while burning plus
+//! gravity, drag and steering losses.
+
+use std::f64::consts::PI;
+
+use bodies::{Atmosphere, Body};
+use engines::Engine;
+use rocket::Rocket;
+use stage::{Stage, GRAVITY};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AscentConfig {
+    /// Altitude of the orbit to reach, in m
+    pub target_altitude: f64,
+    /// Speed at which to start the gravity turn, in m/s
+    pub pitch_speed: f64,
+    /// Angle from vertical to pitch over to, in degrees
+    pub pitch_angle: f64,
+    /// Drag coefficient times cross sectional area, in m²
+    pub drag_area: f64,
+    /// Eastward speed of the launch site due to the planet's rotation, in m/s
+    pub surface_speed: f64,
+    /// Integration step, in s
+    pub time_step: f64,
+    /// Throttle down to stay under this many g, as far as the engines allow
+    pub max_g_force: Option<f64>,
+}
+
+impl Default for AscentConfig {
+    fn default() -> Self {
+        AscentConfig {
+            target_altitude: 180_000.0,
+            pitch_speed: 80.0,
+            pitch_angle: 2.5,
+            drag_area: 3.0,
+            surface_speed: 0.0,
+            time_step: 0.1,
+            max_g_force: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AscentResult {
+    pub reached_orbit: bool,
+    /// Apoapsis altitude, in m. Infinite if the rocket escaped.
+    pub apoapsis: f64,
+    /// Periapsis altitude, in m. Negative if the orbit intersects the surface.
+    pub periapsis: f64,
+    pub orbital_speed: f64,
+    pub gravity_loss: f64,
+    pub drag_loss: f64,
+    pub steering_loss: f64,
+    pub delta_v_expended: f64,
+    /// Highest dynamic pressure seen, in Pa
+    pub max_dynamic_pressure: f64,
+    /// Highest acceleration from thrust, in g
+    pub max_g_force: f64,
+    /// Time from lift-off to engine cut-off (or running dry), in s
+    pub time: f64,
+    /// Number of stages from `Rocket::stages` that were ignited
+    pub stages_used: usize,
+}
+
+struct State {
+    position: Vector,
+    velocity: Vector,
+    time: f64,
+}
+
+pub fn simulate(rocket: &Rocket, body: &Body, config: &AscentConfig) -> AscentResult {
+    fly(rocket, body, config, f64::INFINITY).0
+}
+
+/// Burn time from lift-off until the rocket first climbs to `altitude`, in
+/// s. Coasts don't count, and burn times are at a throttle of 1, as for
+/// `Stage::burn_time`. `None` if it reaches orbit or falls back without
+/// getting there.
+pub fn time_to_altitude(rocket: &Rocket, body: &Body, config: &AscentConfig, altitude: f64) -> Option<f64> {
+    fly(rocket, body, config, altitude).1
+}
+
+/// Simulates the ascent, stopping early if it reaches `stop_altitude`.
+/// Also returns the burn time up to there if it did.
+fn fly(rocket: &Rocket, body: &Body, config: &AscentConfig, stop_altitude: f64) -> (AscentResult, Option<f64>) {
+    let mu = body.gravitational_parameter;
+    let radius = body.radius;
+    let atmosphere = body.atmosphere.unwrap_or(Atmosphere {
+        surface_pressure: 0.0,
+        surface_density: 0.0,
+        scale_height: 1.0,
+        height: 0.0,
+    });
+    let dt = config.time_step;
+    let pitch_angle = config.pitch_angle * PI / 180.0;
+
+    let mut state = State {
+        position: Vector(0.0, radius),
+        velocity: Vector(config.surface_speed, 0.0),
+        time: 0.0,
+    };
+    let mut result = AscentResult {
+        reached_orbit: false,
+        apoapsis: 0.0,
+        periapsis: -radius,
+        orbital_speed: config.surface_speed,
+        gravity_loss: 0.0,
+        drag_loss: 0.0,
+        steering_loss: 0.0,
+        delta_v_expended: 0.0,
+        max_dynamic_pressure: 0.0,
+        max_g_force: 0.0,
+        time: 0.0,
+        stages_used: 0,
+    };
+    let mut turning = false;
+    let mut stopped = false;
+    let mut burn_time_used = 0.0;
+
+    'stages: for stage in rocket.stages() {
+        result.stages_used += 1;
+        let engines = stage.engines();
+        let burn_time = stage.burn_time();
+        let mut mass = stage.wet_mass();
+        let mut stage_time = 0.0;
+
+        while stage_time < burn_time {
+            let r = state.position.length();
+            let up = state.position.unit();
+            let altitude = r - radius;
+            let pressure = atmosphere.pressure(altitude);
+
+            // The atmosphere rotates with the surface
+            let air_velocity = up.perpendicular().scale(config.surface_speed * r / radius);
+            let surface_velocity = state.velocity.sub(air_velocity);
+            let airspeed = surface_velocity.length();
+            let dynamic_pressure = 0.5 * atmosphere.density(altitude) * airspeed * airspeed;
+            result.max_dynamic_pressure = result.max_dynamic_pressure.max(dynamic_pressure);
+
+            let (apoapsis, periapsis) = orbit(&state, mu, radius);
+            if altitude >= stop_altitude {
+                stopped = true;
+                break 'stages;
+            }
+            if altitude > atmosphere.height && periapsis >= config.target_altitude {
+                result.reached_orbit = true;
+                break 'stages;
+            }
+
+            let active = engines.iter().filter(|e| e.burn_time > stage_time);
+            let mut thrust = active.clone().map(|e| e.thrust_at(pressure) * 1000.0).sum::<f64>();
+            let mut mass_flow = active.clone().map(Engine::propellant_mass_per_second).sum::<f64>();
+
+            let throttle = if thrust > 0.0 {
+                let limit = |throttle: fn(&Engine) -> f64| active.clone()
+                    .map(|e| e.thrust_at(pressure) * 1000.0 * throttle(e))
+                    .sum::<f64>() / thrust;
+                let (min_throttle, max_throttle) = (limit(|e| e.min_throttle), limit(|e| e.max_throttle));
+                match config.max_g_force {
+                    Some(max_g_force) => (max_g_force * GRAVITY * mass / thrust).min(max_throttle).max(min_throttle),
+                    None => max_throttle,
+                }
+            } else {
+                1.0
+            };
+            thrust *= throttle;
+            mass_flow *= throttle;
+
+            let direction = if altitude > atmosphere.height && apoapsis >= config.target_altitude {
+                // Coast until we're half the circularization burn from apoapsis
+                let circular_speed = (mu / (radius + apoapsis)).sqrt();
+                let horizontal_speed = state.velocity.dot(up.perpendicular()) * r
+                    / (radius + apoapsis);
+                let burn_time = (circular_speed - horizontal_speed) * mass / thrust;
+                let time_to_apoapsis = state.velocity.dot(up) / (mu / (r * r));
+                if time_to_apoapsis > burn_time / 2.0 {
+                    thrust = 0.0;
+                    mass_flow = 0.0;
+                }
+                // Hold altitude, using whatever thrust is left to speed up.
+                // Gravity takes off any speed still climbing, so thrusting
+                // down to stop it sooner would only waste delta-v.
+                let east = up.perpendicular();
+                let gravity = mu / (r * r);
+                let centrifugal = state.velocity.dot(east).powi(2) / r;
+                let radial = (gravity - centrifugal - state.velocity.dot(up) / 10.0)
+                    / (thrust / mass).max(1e-9);
+                let radial = radial.clamp(0.0, 1.0);
+                up.scale(radial).add(east.scale((1.0 - radial * radial).sqrt()))
+            } else if altitude > atmosphere.height {
+                state.velocity.unit()
+            } else if !turning {
+                if airspeed >= config.pitch_speed {
+                    turning = true;
+                }
+                up
+            } else if surface_velocity.angle_to(up) < pitch_angle {
+                up.rotate(-pitch_angle)
+            } else {
+                surface_velocity.unit()
+            };
+
+            let gravity = up.scale(-mu / (r * r));
+            let drag = surface_velocity.unit()
+                .scale(-dynamic_pressure * config.drag_area / mass);
+            let acceleration = direction.scale(thrust / mass);
+
+            if thrust > 0.0 {
+                let prograde = state.velocity.unit();
+                let thrust_acceleration = thrust / mass;
+                result.max_g_force = result.max_g_force.max(thrust_acceleration / GRAVITY);
+                result.delta_v_expended += thrust_acceleration * dt;
+                result.steering_loss += (thrust_acceleration - acceleration.dot(prograde)) * dt;
+                result.gravity_loss -= gravity.dot(prograde) * dt;
+                result.drag_loss -= drag.dot(prograde) * dt;
+            }
+
+            state.velocity = state.velocity
+                .add(gravity.add(drag).add(acceleration).scale(dt));
+            state.position = state.position.add(state.velocity.scale(dt));
+            state.time += dt;
+            if thrust > 0.0 {
+                // Engine burn times are at a throttle of 1
+                stage_time += dt * throttle;
+                burn_time_used += dt * throttle;
+                mass -= mass_flow * dt;
+            }
+
+            if state.position.length() < radius {
+                break 'stages;
+            }
+        }
+    }
+
+    let (apoapsis, periapsis) = orbit(&state, mu, radius);
+    result.apoapsis = apoapsis;
+    result.periapsis = periapsis;
+    result.orbital_speed = state.velocity.length();
+    result.time = state.time;
+    (result, if stopped { Some(burn_time_used) } else { None })
+}
+
+/// Apoapsis and periapsis altitudes of the orbit passing through `state`
+fn orbit(state: &State, mu: f64, radius: f64) -> (f64, f64) {
+    let r = state.position.length();
+    let v = state.velocity.length();
+    let energy = v * v / 2.0 - mu / r;
+    let angular_momentum = state.position.cross(state.velocity);
+    let eccentricity = (1.0 + 2.0 * energy * angular_momentum * angular_momentum / (mu * mu))
+        .max(0.0).sqrt();
+
+    if energy >= 0.0 {
+        let periapsis = angular_momentum * angular_momentum / mu / (1.0 + eccentricity);
+        (f64::INFINITY, periapsis - radius)
+    } else {
+        let semi_major_axis = -mu / (2.0 * energy);
+        (
+            semi_major_axis * (1.0 + eccentricity) - radius,
+            semi_major_axis * (1.0 - eccentricity) - radius,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Vector(f64, f64);
+
+impl Vector {
+    fn add(self, other: Vector) -> Vector {
+        Vector(self.0 + other.0, self.1 + other.1)
+    }
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector(self.0 - other.0, self.1 - other.1)
+    }
+
+    fn scale(self, factor: f64) -> Vector {
+        Vector(self.0 * factor, self.1 * factor)
+    }
+
+    fn dot(self, other: Vector) -> f64 {
+        self.0 * other.0 + self.1 * other.1
+    }
+
+    fn cross(self, other: Vector) -> f64 {
+        self.0 * other.1 - self.1 * other.0
+    }
+
+    fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    fn unit(self) -> Vector {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self.scale(1.0 / length)
+        }
+    }
+
+    /// Rotated 90° clockwise, which is east when `self` points up
+    fn perpendicular(self) -> Vector {
+        Vector(self.1, -self.0)
+    }
+
+    /// Rotated counter-clockwise by `angle` radians
+    fn rotate(self, angle: f64) -> Vector {
+        let (sin, cos) = angle.sin_cos();
+        Vector(self.0 * cos - self.1 * sin, self.0 * sin + self.1 * cos)
+    }
+
+    fn angle_to(self, other: Vector) -> f64 {
+        self.unit().dot(other.unit()).clamp(-1.0, 1.0).acos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bodies::rss::{EARTH, MOON};
+    use catalog::Catalog;
+    use design::RocketDesign;
+    use engines::BELL_8081;
+    use stage::SimpleStage;
+    use toml;
+
+    fn lunar_ascent(min_throttle: f64, max_throttle: f64, max_g_force: Option<f64>) -> AscentResult {
+        let engine = Engine { min_throttle, max_throttle, ..BELL_8081 };
+        let rocket = Rocket { stages: vec![Box::new(SimpleStage { dry_mass: 700.0, engines: vec![engine] })], payload_mass: 0.0 };
+        let config = AscentConfig { target_altitude: MOON.low_orbit_altitude, max_g_force, ..AscentConfig::default() };
+        simulate(&rocket, &MOON, &config)
+    }
+
+    #[test]
+    fn atlas_agena_steers_efficiently() {
+        let design: RocketDesign = toml::from_str(include_str!("../examples/atlas_agena.toml")).unwrap();
+        let rocket = design.build(&Catalog::builtin()).unwrap();
+        let config = AscentConfig { target_altitude: EARTH.low_orbit_altitude, ..AscentConfig::default() };
+        let result = simulate(&rocket, &EARTH, &config);
+
+        assert!(result.reached_orbit);
+        // Steering only turns the thrust off prograde to hold the altitude
+        // at the end, rather than fighting the climb
+        assert!(result.steering_loss < 500.0, "{:?}", result);
+        assert!(result.delta_v_expended < 10_500.0, "{:?}", result);
+    }
+
+    #[test]
+    fn engines_throttle_down_as_far_as_they_can() {
+        // Unlimited, the 71 kN engine reaches 2.36 g before orbit
+        let result = lunar_ascent(0.2, 1.0, Some(2.0));
+        assert!(result.max_g_force < 2.0 + 1e-6, "{:?}", result);
+        assert!(result.max_g_force > 1.99, "{:?}", result);
+
+        let result = lunar_ascent(1.0, 1.0, Some(2.0));
+        assert!(result.max_g_force > 2.3, "{:?}", result);
+    }
+
+    #[test]
+    fn full_throttle_is_the_engines_max_throttle() {
+        let full = lunar_ascent(1.0, 1.0, None);
+        let derated = lunar_ascent(0.5, 0.5, None);
+        // Half the thrust, on a little more mass for the longer burn
+        let ratio = derated.max_g_force / full.max_g_force;
+        assert!(ratio > 0.5 && ratio < 0.52, "{:?}\n{:?}", full, derated);
+        assert!(derated.gravity_loss > full.gravity_loss, "{:?}\n{:?}", full, derated);
+    }
+}
@@ -0,0 +1,471 @@
+//! Planets and moons, and the delta-v needed to move between them.
+//!
+//! All distances are in metres and all speeds in m/s. Transfers are
+//! Hohmann transfers between circular, coplanar orbits, so they ignore plane
+//! changes, eccentricity and gravity assists.
+
+use std::f64::consts::PI;
+
+/// An exponential model of an atmosphere, which ends abruptly at `height`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    /// Pressure at the surface, in atmospheres
+    pub surface_pressure: f64,
+    /// Density at the surface, in kg/m³
+    pub surface_density: f64,
+    pub scale_height: f64,
+    pub height: f64,
+}
+
+impl Atmosphere {
+    pub fn pressure(&self, altitude: f64) -> f64 {
+        self.surface_pressure * self.falloff(altitude)
+    }
+
+    pub fn density(&self, altitude: f64) -> f64 {
+        self.surface_density * self.falloff(altitude)
+    }
+
+    fn falloff(&self, altitude: f64) -> f64 {
+        if altitude >= self.height {
+            0.0
+        } else {
+            (-altitude.max(0.0) / self.scale_height).exp()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbit {
+    pub parent: &'static Body,
+    pub semi_major_axis: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Body {
+    pub name: &'static str,
+    /// GM, in m³/s²
+    pub gravitational_parameter: f64,
+    pub radius: f64,
+    /// Sidereal rotation period, in s
+    pub rotation_period: f64,
+    pub atmosphere: Option<Atmosphere>,
+    /// `None` for the star at the center of a system
+    pub orbit: Option<Orbit>,
+    /// Altitude of the lowest orbit worth parking in, clear of the atmosphere
+    /// and terrain
+    pub low_orbit_altitude: f64,
+}
+
+impl Body {
+    pub fn parent(&self) -> Option<&'static Body> {
+        self.orbit.map(|o| o.parent)
+    }
+
+    /// Size of the body's orbit around its parent. Zero for stars.
+    pub fn semi_major_axis(&self) -> f64 {
+        self.orbit.map(|o| o.semi_major_axis).unwrap_or(0.0)
+    }
+
+    /// Radius of the sphere of influence. Infinite for stars.
+    pub fn sphere_of_influence(&self) -> f64 {
+        match self.orbit {
+            Some(orbit) => orbit.semi_major_axis *
+                (self.gravitational_parameter / orbit.parent.gravitational_parameter).powf(0.4),
+            None => f64::INFINITY,
+        }
+    }
+
+    pub fn low_orbit_radius(&self) -> f64 {
+        self.radius + self.low_orbit_altitude
+    }
+
+    /// Radius of the orbit whose period matches the body's rotation
+    pub fn synchronous_orbit_radius(&self) -> f64 {
+        (self.gravitational_parameter * self.rotation_period.powi(2) / (4.0 * PI * PI)).cbrt()
+    }
+
+    /// Speed of a circular orbit with the given radius
+    pub fn circular_speed(&self, radius: f64) -> f64 {
+        vis_viva(self.gravitational_parameter, radius, radius)
+    }
+
+    pub fn escape_speed(&self, radius: f64) -> f64 {
+        (2.0 * self.gravitational_parameter / radius).sqrt()
+    }
+
+    /// Delta-v to go from a circular orbit at `radius` to escape with
+    /// `excess_speed` left over.
+    pub fn departure_delta_v(&self, radius: f64, excess_speed: f64) -> f64 {
+        (excess_speed.powi(2) + self.escape_speed(radius).powi(2)).sqrt() -
+            self.circular_speed(radius)
+    }
+
+    /// Delta-v to capture into low orbit when arriving with `excess_speed`.
+    pub fn capture_delta_v(&self, excess_speed: f64) -> f64 {
+        self.departure_delta_v(self.low_orbit_radius(), excess_speed)
+    }
+
+    /// Delta-v to land from low orbit. Bodies with an atmosphere only need a
+    /// deorbit burn, the rest is left to heat shields and parachutes.
+    pub fn landing_delta_v(&self) -> f64 {
+        let mu = self.gravitational_parameter;
+        let (deorbit, _) = hohmann(mu, self.low_orbit_radius(), self.radius);
+        if self.atmosphere.is_some() {
+            deorbit
+        } else {
+            let semi_major_axis = (self.low_orbit_radius() + self.radius) / 2.0;
+            deorbit + vis_viva(mu, self.radius, semi_major_axis)
+        }
+    }
+}
+
+/// Speed at `radius` on an orbit with the given semi-major axis
+pub fn vis_viva(gravitational_parameter: f64, radius: f64, semi_major_axis: f64) -> f64 {
+    (gravitational_parameter * (2.0 / radius - 1.0 / semi_major_axis)).sqrt()
+}
+
+/// The two burns of a Hohmann transfer between circular orbits
+pub fn hohmann(gravitational_parameter: f64, from: f64, to: f64) -> (f64, f64) {
+    let mu = gravitational_parameter;
+    let transfer = (from + to) / 2.0;
+    (
+        (vis_viva(mu, from, transfer) - vis_viva(mu, from, from)).abs(),
+        (vis_viva(mu, to, to) - vis_viva(mu, to, transfer)).abs(),
+    )
+}
+
+/// A star and everything orbiting it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct System {
+    pub name: &'static str,
+    pub bodies: &'static [Body],
+    /// The body rockets launch from
+    pub home: &'static Body,
+    /// Delta-v to reach low orbit from the launch site, including losses
+    pub launch_delta_v: f64,
+}
+
+impl System {
+    pub fn body(&self, name: &str) -> Option<&'static Body> {
+        self.bodies.iter().find(|b| b.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn moons_of(&self, body: &Body) -> Vec<&'static Body> {
+        self.bodies.iter().filter(|b| b.parent() == Some(body)).collect()
+    }
+}
+
+pub const SYSTEMS: &[System] = &[rss::SYSTEM, stock::SYSTEM];
+
+/// The Real Solar System
+pub mod rss {
+    use super::*;
+
+    pub const SYSTEM: System = System {
+        name: "rss",
+        bodies: &[SUN, MERCURY, VENUS, EARTH, MOON, MARS, JUPITER, SATURN],
+        home: &EARTH,
+        launch_delta_v: 9400.0,
+    };
+
+    pub const SUN: Body = Body {
+        name: "Sun",
+        gravitational_parameter: 1.327_124_400_18e20,
+        radius: 696_342_000.0,
+        rotation_period: 2_192_832.0,
+        atmosphere: None,
+        orbit: None,
+        low_orbit_altitude: 0.0,
+    };
+
+    pub const MERCURY: Body = Body {
+        name: "Mercury",
+        gravitational_parameter: 2.2032e13,
+        radius: 2_439_700.0,
+        rotation_period: 5_067_014.0,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &SUN, semi_major_axis: 57_909_050_000.0 }),
+        low_orbit_altitude: 100_000.0,
+    };
+
+    pub const VENUS: Body = Body {
+        name: "Venus",
+        gravitational_parameter: 3.248_59e14,
+        radius: 6_051_800.0,
+        rotation_period: 20_997_360.0,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 92.0,
+            surface_density: 65.0,
+            scale_height: 15_900.0,
+            height: 145_000.0,
+        }),
+        orbit: Some(Orbit { parent: &SUN, semi_major_axis: 108_208_000_000.0 }),
+        low_orbit_altitude: 250_000.0,
+    };
+
+    pub const EARTH: Body = Body {
+        name: "Earth",
+        gravitational_parameter: 3.986_004_418e14,
+        radius: 6_371_000.0,
+        rotation_period: 86_164.1,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 1.0,
+            surface_density: 1.225,
+            scale_height: 7200.0,
+            height: 140_000.0,
+        }),
+        orbit: Some(Orbit { parent: &SUN, semi_major_axis: 149_598_023_000.0 }),
+        low_orbit_altitude: 200_000.0,
+    };
+
+    pub const MOON: Body = Body {
+        name: "Moon",
+        gravitational_parameter: 4.904_869_5e12,
+        radius: 1_737_100.0,
+        rotation_period: 2_360_591.5,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &EARTH, semi_major_axis: 384_399_000.0 }),
+        low_orbit_altitude: 100_000.0,
+    };
+
+    pub const MARS: Body = Body {
+        name: "Mars",
+        gravitational_parameter: 4.282_837e13,
+        radius: 3_389_500.0,
+        rotation_period: 88_642.7,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 0.006,
+            surface_density: 0.020,
+            scale_height: 11_100.0,
+            height: 125_000.0,
+        }),
+        orbit: Some(Orbit { parent: &SUN, semi_major_axis: 227_939_200_000.0 }),
+        low_orbit_altitude: 150_000.0,
+    };
+
+    pub const JUPITER: Body = Body {
+        name: "Jupiter",
+        gravitational_parameter: 1.266_865_34e17,
+        radius: 69_911_000.0,
+        rotation_period: 35_730.0,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 1.0,
+            surface_density: 0.16,
+            scale_height: 27_000.0,
+            height: 1_550_000.0,
+        }),
+        orbit: Some(Orbit { parent: &SUN, semi_major_axis: 778_570_000_000.0 }),
+        low_orbit_altitude: 1_600_000.0,
+    };
+
+    pub const SATURN: Body = Body {
+        name: "Saturn",
+        gravitational_parameter: 3.793_118_7e16,
+        radius: 58_232_000.0,
+        rotation_period: 38_362.0,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 1.0,
+            surface_density: 0.19,
+            scale_height: 59_500.0,
+            height: 2_000_000.0,
+        }),
+        orbit: Some(Orbit { parent: &SUN, semi_major_axis: 1_433_530_000_000.0 }),
+        low_orbit_altitude: 2_100_000.0,
+    };
+}
+
+/// The stock Kerbol system
+pub mod stock {
+    use super::*;
+
+    pub const SYSTEM: System = System {
+        name: "stock",
+        bodies: &[KERBOL, MOHO, EVE, KERBIN, MUN, MINMUS, DUNA, IKE, DRES, JOOL, EELOO],
+        home: &KERBIN,
+        launch_delta_v: 3400.0,
+    };
+
+    pub const KERBOL: Body = Body {
+        name: "Kerbol",
+        gravitational_parameter: 1.172_332_8e18,
+        radius: 261_600_000.0,
+        rotation_period: 432_000.0,
+        atmosphere: None,
+        orbit: None,
+        low_orbit_altitude: 0.0,
+    };
+
+    pub const MOHO: Body = Body {
+        name: "Moho",
+        gravitational_parameter: 1.686_093_8e11,
+        radius: 250_000.0,
+        rotation_period: 1_210_000.0,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &KERBOL, semi_major_axis: 5_263_138_304.0 }),
+        low_orbit_altitude: 20_000.0,
+    };
+
+    pub const EVE: Body = Body {
+        name: "Eve",
+        gravitational_parameter: 8.171_730_2e12,
+        radius: 700_000.0,
+        rotation_period: 80_500.0,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 5.0,
+            surface_density: 6.2,
+            scale_height: 7000.0,
+            height: 90_000.0,
+        }),
+        orbit: Some(Orbit { parent: &KERBOL, semi_major_axis: 9_832_684_544.0 }),
+        low_orbit_altitude: 100_000.0,
+    };
+
+    pub const KERBIN: Body = Body {
+        name: "Kerbin",
+        gravitational_parameter: 3.531_6e12,
+        radius: 600_000.0,
+        rotation_period: 21_549.425,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 1.0,
+            surface_density: 1.225,
+            scale_height: 5600.0,
+            height: 70_000.0,
+        }),
+        orbit: Some(Orbit { parent: &KERBOL, semi_major_axis: 13_599_840_256.0 }),
+        low_orbit_altitude: 80_000.0,
+    };
+
+    pub const MUN: Body = Body {
+        name: "Mun",
+        gravitational_parameter: 6.513_839_8e10,
+        radius: 200_000.0,
+        rotation_period: 138_984.38,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &KERBIN, semi_major_axis: 12_000_000.0 }),
+        low_orbit_altitude: 15_000.0,
+    };
+
+    pub const MINMUS: Body = Body {
+        name: "Minmus",
+        gravitational_parameter: 1.765_8e9,
+        radius: 60_000.0,
+        rotation_period: 40_400.0,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &KERBIN, semi_major_axis: 47_000_000.0 }),
+        low_orbit_altitude: 10_000.0,
+    };
+
+    pub const DUNA: Body = Body {
+        name: "Duna",
+        gravitational_parameter: 3.013_632_1e11,
+        radius: 320_000.0,
+        rotation_period: 65_517.859,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 0.0667,
+            surface_density: 0.0149,
+            scale_height: 5700.0,
+            height: 50_000.0,
+        }),
+        orbit: Some(Orbit { parent: &KERBOL, semi_major_axis: 20_726_155_264.0 }),
+        low_orbit_altitude: 60_000.0,
+    };
+
+    pub const IKE: Body = Body {
+        name: "Ike",
+        gravitational_parameter: 1.856_822_8e10,
+        radius: 130_000.0,
+        rotation_period: 65_517.862,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &DUNA, semi_major_axis: 3_200_000.0 }),
+        low_orbit_altitude: 15_000.0,
+    };
+
+    pub const DRES: Body = Body {
+        name: "Dres",
+        gravitational_parameter: 2.148_448_9e10,
+        radius: 138_000.0,
+        rotation_period: 34_800.0,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &KERBOL, semi_major_axis: 40_839_348_203.0 }),
+        low_orbit_altitude: 15_000.0,
+    };
+
+    pub const JOOL: Body = Body {
+        name: "Jool",
+        gravitational_parameter: 2.825_28e14,
+        radius: 6_000_000.0,
+        rotation_period: 36_000.0,
+        atmosphere: Some(Atmosphere {
+            surface_pressure: 15.0,
+            surface_density: 15.0,
+            scale_height: 10_000.0,
+            height: 200_000.0,
+        }),
+        orbit: Some(Orbit { parent: &KERBOL, semi_major_axis: 68_773_560_320.0 }),
+        low_orbit_altitude: 210_000.0,
+    };
+
+    pub const EELOO: Body = Body {
+        name: "Eeloo",
+        gravitational_parameter: 7.441_081_5e10,
+        radius: 210_000.0,
+        rotation_period: 19_460.0,
+        atmosphere: None,
+        orbit: Some(Orbit { parent: &KERBOL, semi_major_axis: 90_118_820_000.0 }),
+        low_orbit_altitude: 10_000.0,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(expected: f64, actual: f64, tolerance: f64) -> bool {
+        (expected - actual).abs() < tolerance
+    }
+
+    #[test]
+    fn leo_to_geo_takes_3_9_km_s() {
+        let (departure, arrival) = hohmann(rss::EARTH.gravitational_parameter, rss::EARTH.low_orbit_radius(), 42_164_000.0);
+        assert!(close(2456.7, departure, 0.1), "{}", departure);
+        assert!(close(1478.0, arrival, 0.1), "{}", arrival);
+
+        // Going down takes the same burns in reverse
+        let (departure, arrival) = hohmann(rss::EARTH.gravitational_parameter, 42_164_000.0, rss::EARTH.low_orbit_radius());
+        assert!(close(1478.0, departure, 0.1), "{}", departure);
+        assert!(close(2456.7, arrival, 0.1), "{}", arrival);
+    }
+
+    #[test]
+    fn vis_viva_gives_orbit_and_escape_speeds() {
+        assert!(close(2278.9, stock::KERBIN.circular_speed(stock::KERBIN.low_orbit_radius()), 0.1));
+        assert!(close(11_186.1, rss::EARTH.escape_speed(rss::EARTH.radius), 0.1));
+        let mu = rss::EARTH.gravitational_parameter;
+        assert!(close(rss::EARTH.escape_speed(7e6), vis_viva(mu, 7e6, f64::INFINITY), 1e-9));
+    }
+
+    #[test]
+    fn kerbin_matches_the_wiki() {
+        assert!(close(2_863_334.0, stock::KERBIN.synchronous_orbit_radius() - stock::KERBIN.radius, 1.0));
+        assert!(close(84_159_286.0, stock::KERBIN.sphere_of_influence(), 1.0));
+        assert!(close(2_429_559.1, stock::MUN.sphere_of_influence(), 0.1));
+        assert_eq!(f64::INFINITY, stock::KERBOL.sphere_of_influence());
+    }
+
+    #[test]
+    fn capture_burns_leave_the_excess_speed_behind() {
+        // (√2 - 1) times the orbital speed with nothing to spare
+        assert!(close(943.96, stock::KERBIN.capture_delta_v(0.0), 0.01));
+        assert!(close(1095.54, stock::KERBIN.capture_delta_v(1000.0), 0.01));
+    }
+
+    #[test]
+    fn landings_brake_all_the_way_without_an_atmosphere() {
+        // Deorbit from 200 km, and the heat shield does the rest
+        assert!(close(60.41, rss::EARTH.landing_delta_v(), 0.01));
+        // Deorbit from 15 km, then brake from the transfer orbit's speed at
+        // the surface
+        assert!(close(590.96, stock::MUN.landing_delta_v(), 0.01));
+    }
+}
@@ -0,0 +1,328 @@
+//! Engine and fuel catalogs loaded from data files.
+//!
+//! A catalog file is either TOML or JSON, and lists fuels and engines.
+//! Engines refer to fuels by name, which can be any fuel defined in the same
+//! file or already present in the catalog (including the built-in ones).
+//!
+//! ```toml
+//! [[fuels]]
+//! name = "Ethanol75"
+//! density = 0.8625
+//!
+//! [[fuels]]
+//! name = "LqdMethane"
+//! density = 0.42
+//! boiloff_rate = 0.008
+//!
+//! [[engines]]
+//! name = "A-4"
+//! fuel_consumption = { Ethanol75 = 60.1, LqdOxygen = 55.6 }
+//! isp = 239.0
+//! sea_level_isp = 203.0
+//! thrust = 311.0
+//! mass = 935.0
+//! burn_time = 65.0
+//! cost = 1200.0
+//! ignitions = 1
+//! ullage = true
+//! min_throttle = 0.6
+//! ignition_reliability = 0.95
+//! cycle_reliability = 0.97
+//! ```
+//!
+//! Engine costs and fuel boiloff rates (the fraction lost per day in an
+//! uninsulated tank) are optional and default to zero. Engines without
+//! `ignitions` can be relit any number of times, and only need ullage when
+//! `ullage` is set. Throttle limits default to 1, for engines which can't
+//! throttle. `rated_burn_time` defaults to `burn_time`, and reliabilities
+//! default to 1. Engines with `vernier` set are only used in pairs as a
+//! stage's `verniers`.
+//!
+//! Fuel densities, thrust, Isp, mass and burn times must be positive, with
+//! `sea_level_isp` at most `isp`. `min_throttle` must be positive and at most
+//! `max_throttle`, and reliabilities between 0 and 1.
+//!
+//! Entries with the same name as an existing fuel or engine replace it.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use data_file::{self, Format};
+use engines::{self, Engine};
+use error::Error;
+use fuels::{self, Fuel};
+
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    fuels: Vec<Fuel>,
+    engines: Vec<Engine>,
+}
+
+impl Catalog {
+    /// A catalog containing every fuel and engine defined in `fuels` and
+    /// `engines`.
+    pub fn builtin() -> Self {
+        Catalog {
+            fuels: fuels::ALL.to_vec(),
+            engines: engines::ALL.to_vec(),
+        }
+    }
+
+    pub fn fuels(&self) -> &[Fuel] {
+        &self.fuels
+    }
+
+    pub fn engines(&self) -> &[Engine] {
+        &self.engines
+    }
+
+    pub fn fuel(&self, name: &str) -> Option<&Fuel> {
+        self.fuels.iter().find(|f| f.name == name)
+    }
+
+    pub fn engine(&self, name: &str) -> Option<&Engine> {
+        self.engines.iter().find(|e| e.name == name)
+    }
+
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let file = data_file::read(path)?;
+        self.add(file).map_err(|e| e.in_file(path))
+    }
+
+    pub fn load_toml(&mut self, source: &str) -> Result<(), Error> {
+        let file = Format::Toml.parse(source)?;
+        self.add(file)
+    }
+
+    pub fn load_json(&mut self, source: &str) -> Result<(), Error> {
+        let file = Format::Json.parse(source)?;
+        self.add(file)
+    }
+
+    fn add(&mut self, file: CatalogFile) -> Result<(), Error> {
+        for fuel in file.fuels {
+            fuel.validate()?;
+            self.insert_fuel(Fuel {
+                name: Cow::Owned(fuel.name),
+                density: fuel.density,
+                boiloff_rate: fuel.boiloff_rate,
+            });
+        }
+
+        for engine in file.engines {
+            engine.validate()?;
+            let fuel_consumption = engine.fuel_consumption.iter()
+                .map(|(fuel, &rate)| match self.fuel(fuel) {
+                    Some(f) => Ok((f.clone(), rate)),
+                    None => Err(Error::UnknownFuel {
+                        engine: engine.name.clone(),
+                        fuel: fuel.clone(),
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            self.insert_engine(Engine {
+                name: Cow::Owned(engine.name),
+                fuel_consumption: Cow::Owned(fuel_consumption),
+                isp: engine.isp,
+                sea_level_isp: engine.sea_level_isp,
+                thrust: engine.thrust,
+                mass: engine.mass,
+                burn_time: engine.burn_time,
+                cost: engine.cost,
+                ignitions: engine.ignitions,
+                ullage: engine.ullage,
+                min_throttle: engine.min_throttle,
+                max_throttle: engine.max_throttle,
+                rated_burn_time: engine.rated_burn_time.unwrap_or(engine.burn_time),
+                ignition_reliability: engine.ignition_reliability,
+                cycle_reliability: engine.cycle_reliability,
+                vernier: engine.vernier,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn insert_fuel(&mut self, fuel: Fuel) {
+        match self.fuels.iter().position(|f| f.name == fuel.name) {
+            Some(i) => self.fuels[i] = fuel,
+            None => self.fuels.push(fuel),
+        }
+    }
+
+    /// Adds `engine`, replacing any engine with the same name
+    pub fn insert_engine(&mut self, engine: Engine) {
+        match self.engines.iter().position(|e| e.name == engine.name) {
+            Some(i) => self.engines[i] = engine,
+            None => self.engines.push(engine),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CatalogFile {
+    #[serde(default)]
+    fuels: Vec<FuelEntry>,
+    #[serde(default)]
+    engines: Vec<EngineEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FuelEntry {
+    name: String,
+    density: f64,
+    #[serde(default)]
+    boiloff_rate: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EngineEntry {
+    name: String,
+    fuel_consumption: BTreeMap<String, f64>,
+    isp: f64,
+    sea_level_isp: f64,
+    thrust: f64,
+    mass: f64,
+    burn_time: f64,
+    #[serde(default)]
+    cost: f64,
+    ignitions: Option<u32>,
+    #[serde(default)]
+    ullage: bool,
+    #[serde(default = "one")]
+    min_throttle: f64,
+    #[serde(default = "one")]
+    max_throttle: f64,
+    rated_burn_time: Option<f64>,
+    #[serde(default = "one")]
+    ignition_reliability: f64,
+    #[serde(default = "one")]
+    cycle_reliability: f64,
+    #[serde(default)]
+    vernier: bool,
+}
+
+impl FuelEntry {
+    fn validate(&self) -> Result<(), Error> {
+        if !(self.density.is_finite() && self.density > 0.0) {
+            return Err(Error::InvalidFuel { fuel: self.name.clone(), field: "density", requirement: "must be positive" });
+        }
+        Ok(())
+    }
+}
+
+impl EngineEntry {
+    fn validate(&self) -> Result<(), Error> {
+        let rated_burn_time = self.rated_burn_time.unwrap_or(self.burn_time);
+        let positive = [
+            ("isp", self.isp),
+            ("sea_level_isp", self.sea_level_isp),
+            ("thrust", self.thrust),
+            ("mass", self.mass),
+            ("burn_time", self.burn_time),
+            ("rated_burn_time", rated_burn_time),
+            ("max_throttle", self.max_throttle),
+        ];
+        if let Some(&(field, _)) = positive.iter().find(|&&(_, value)| !(value.is_finite() && value > 0.0)) {
+            return Err(self.invalid(field, "must be positive"));
+        }
+        if self.sea_level_isp > self.isp {
+            return Err(self.invalid("sea_level_isp", "must be at most isp"));
+        }
+        if !(self.min_throttle > 0.0 && self.min_throttle <= self.max_throttle) {
+            return Err(self.invalid("min_throttle", "must be positive and at most max_throttle"));
+        }
+        let chances = [
+            ("ignition_reliability", self.ignition_reliability),
+            ("cycle_reliability", self.cycle_reliability),
+        ];
+        if let Some(&(field, _)) = chances.iter().find(|&&(_, value)| !(0.0..=1.0).contains(&value)) {
+            return Err(self.invalid(field, "must be between 0 and 1"));
+        }
+        Ok(())
+    }
+
+    fn invalid(&self, field: &'static str, requirement: &'static str) -> Error {
+        Error::InvalidEngine { engine: self.name.clone(), field, requirement }
+    }
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads an engine with `field` set to `value`
+    fn load_with(field: &str, value: &str) -> Result<Catalog, Error> {
+        let fields = [
+            ("isp", "239.0"),
+            ("sea_level_isp", "203.0"),
+            ("thrust", "311.0"),
+            ("mass", "935.0"),
+            ("burn_time", "65.0"),
+            ("rated_burn_time", "70.0"),
+            ("ignition_reliability", "0.95"),
+            ("cycle_reliability", "0.97"),
+            ("min_throttle", "0.6"),
+            ("max_throttle", "1.0"),
+        ];
+        let mut source = "[[engines]]\nname = \"A-4\"\nfuel_consumption = { Kerosene = 60.1, LqdOxygen = 55.6 }\n".to_string();
+        for &(name, default) in &fields {
+            source += &format!("{} = {}\n", name, if name == field { value } else { default });
+        }
+        let mut catalog = Catalog::builtin();
+        catalog.load_toml(&source)?;
+        Ok(catalog)
+    }
+
+    #[test]
+    fn loads_valid_engines() {
+        let catalog = load_with("", "").unwrap();
+        let engine = catalog.engine("A-4").unwrap();
+        assert_eq!(70.0, engine.rated_burn_time);
+        assert!((engine.reliability() - 0.95 * 0.97f64.powf(65.0 / 70.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rejects_invalid_engines() {
+        let invalid = [
+            ("rated_burn_time", "0.0"),
+            ("thrust", "-1.0"),
+            ("isp", "0.0"),
+            ("mass", "inf"),
+            ("cycle_reliability", "1.2"),
+            ("ignition_reliability", "-0.1"),
+            ("cycle_reliability", "nan"),
+            ("min_throttle", "0.0"),
+            ("min_throttle", "1.1"),
+            ("max_throttle", "-1.0"),
+            ("sea_level_isp", "0.0"),
+            ("sea_level_isp", "240.0"),
+        ];
+        for &(field, value) in &invalid {
+            match load_with(field, value) {
+                Err(Error::InvalidEngine { field: invalid_field, .. }) => assert_eq!(field, invalid_field),
+                other => panic!("{} = {}: {:?}", field, value, other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_fuels() {
+        for density in &["0.0", "-0.8", "nan"] {
+            let source = format!("[[fuels]]\nname = \"Ethanol75\"\ndensity = {}\n", density);
+            match Catalog::builtin().load_toml(&source) {
+                Err(Error::InvalidFuel { field: "density", .. }) => {}
+                other => panic!("density = {}: {:?}", density, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! The subcommands of the command line tool. Each takes its options already
+//! parsed from the arguments, and prints its results with `text`.
+
+use std::io;
+
+use ansi_term::Colour::{Blue, Red, Yellow};
+
+use ascent::{self, AscentConfig};
+use bodies::System;
+use catalog::Catalog;
+use design::RocketDesign;
+use destinations::{payload_target, DeltaVMap};
+use dispersion::{self, Dispersion};
+use error::Error;
+use finite_burn::StartOrbit;
+use mission::MissionPlan;
+use report::Report;
+use rocket::{PayloadTarget, Rocket};
+use search::{self, Ranking, SearchConfig};
+use sizing::size_stages;
+use tanks::TankModel;
+use text::*;
+
+/// How `analyze` writes its results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    /// The stage table of the report, see `Report::write_stages_csv`
+    StagesCsv,
+    DestinationsCsv,
+}
+
+/// Options for `burn`. Altitudes are in m.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurnOptions<'a> {
+    /// Impulsive delta-v of the maneuver
+    pub delta_v: f64,
+    /// Counted from 1 at the bottom, the top stage by default
+    pub stage: Option<usize>,
+    /// The home planet by default
+    pub body: Option<&'a str>,
+    /// Low orbit by default
+    pub periapsis: Option<f64>,
+    /// The periapsis by default
+    pub apoapsis: Option<f64>,
+    /// Loss above which to suggest perigee kicks, as a fraction of the
+    /// delta-v
+    pub max_loss: f64,
+}
+
+pub fn analyze(rocket_path: &str, catalog: &Catalog, system: &System, format: Format, max_g_force: Option<f64>) -> Result<(), Error> {
+    let design = RocketDesign::load(rocket_path)?;
+    let rocket = design.build_for(catalog, system.home)?;
+    let stdout = io::stdout();
+    match format {
+        Format::Json => Report::new(&rocket, system).write_json(stdout.lock())?,
+        Format::StagesCsv => Report::new(&rocket, system).write_stages_csv(stdout.lock())?,
+        Format::DestinationsCsv => Report::new(&rocket, system).write_destinations_csv(stdout.lock())?,
+        Format::Text => {
+            print_stages(&rocket);
+            if let Some(max_g_force) = max_g_force {
+                println!();
+                print_g_limited_burns(&rocket, max_g_force);
+            }
+            if design.fairing.is_some() || design.escape_system.is_some() {
+                println!();
+                print_component_penalty(&design, catalog, system)?;
+            }
+            println!();
+            print_where_rocket_can_go(&rocket, system);
+        }
+    }
+    Ok(())
+}
+
+/// Shows the max payload to `destination`, or to every destination without
+/// one. `target` gives every limit but the delta-v.
+pub fn max_payload(rocket_path: &str, catalog: &Catalog, system: &System, target: PayloadTarget, destination: Option<&str>) -> Result<(), Error> {
+    let rocket = load_rocket(rocket_path, catalog, system)?;
+    match destination {
+        Some(name) => {
+            let (name, required_dv) = payload_target(system, name)?;
+            print_max_payload(&rocket, PayloadTarget { delta_v: required_dv, ..target }, &name);
+        }
+        None => print_max_payloads(&rocket, system, target),
+    }
+    Ok(())
+}
+
+pub fn size(rocket_path: &str, catalog: &Catalog, system: &System, tanks: &TankModel, target: &PayloadTarget) -> Result<(), Error> {
+    let rocket = load_rocket(rocket_path, catalog, system)?;
+    match size_stages(&rocket, tanks, target) {
+        Some(rocket) => print_stages(&rocket),
+        None => println!("{}", Red.bold().paint("No propellant loads meet these requirements")),
+    }
+    Ok(())
+}
+
+pub fn search(catalog: &Catalog, config: &SearchConfig) {
+    if config.ranking == Ranking::Cost && catalog.engines().iter().all(|e| e.cost == 0.0) {
+        println!("{}", Yellow.paint("No engine in the catalog has a cost, so designs are ranked by lift-off mass"));
+        println!();
+    }
+    print_search_results(&search::search(catalog, config));
+}
+
+/// Samples the rocket and shows the spread of its delta-v and of its payload
+/// to `destination`, orbit by default
+pub fn dispersion(rocket_path: &str, catalog: &Catalog, system: &System, dispersion: &Dispersion, destination: Option<&str>, min_liftoff_twr: f64) -> Result<(), Error> {
+    let design = RocketDesign::load(rocket_path)?;
+    let (name, required_dv) = payload_target(system, destination.unwrap_or("orbit"))?;
+    let target = PayloadTarget {
+        delta_v: required_dv,
+        min_liftoff_twr,
+        ..PayloadTarget::default()
+    };
+    let samples = dispersion::run(&design, catalog, system.home, dispersion, &target)?;
+    print_dispersion(&samples, system, &name);
+    Ok(())
+}
+
+pub fn check(rocket_path: &str, mission_path: &str, catalog: &Catalog, system: &System) -> Result<(), Error> {
+    let design = RocketDesign::load(rocket_path)?;
+    let plan = MissionPlan::load(mission_path)?;
+    let issues = plan.validate(&design, catalog)?;
+    if issues.is_empty() {
+        println!("{}", Blue.paint("The mission plan can be flown"));
+    }
+    for issue in issues {
+        println!("{}", Red.paint(issue.to_string()));
+    }
+    println!();
+    let rocket = design.build_for(catalog, system.home)?;
+    print_burns(&plan, &plan.fly(&design, &rocket, catalog)?);
+    Ok(())
+}
+
+pub fn where_rocket_can_go(rocket_path: &str, catalog: &Catalog, system: &System) -> Result<(), Error> {
+    let rocket = load_rocket(rocket_path, catalog, system)?;
+    print_where_rocket_can_go(&rocket, system);
+    Ok(())
+}
+
+/// Shows the cheapest route through `stops`, and whether the rocket can fly
+/// it when given one
+pub fn route(stops: &[&str], aerobrake: bool, rocket_path: Option<&str>, catalog: &Catalog, system: &System) -> Result<(), Error> {
+    let map = DeltaVMap::for_system(system);
+    let route = map.itinerary(stops, aerobrake)?;
+    print_route(&route);
+    if let Some(rocket_path) = rocket_path {
+        let rocket = load_rocket(rocket_path, catalog, system)?;
+        println!();
+        let dv = rocket.delta_v();
+        let destination = route.locations.last().unwrap();
+        if dv > route.delta_v() {
+            print_if_rocket_can_go_to(dv, route.delta_v(), destination);
+        } else {
+            println!("{}", Red.bold().paint(format!("This rocket can't go to {}", destination)));
+        }
+    }
+    Ok(())
+}
+
+pub fn ascent(rocket_path: &str, catalog: &Catalog, system: &System, config: &AscentConfig) -> Result<(), Error> {
+    let rocket = load_rocket(rocket_path, catalog, system)?;
+    print_ascent(&ascent::simulate(&rocket, system.home, config));
+    Ok(())
+}
+
+pub fn burn(rocket_path: &str, catalog: &Catalog, system: &System, options: &BurnOptions) -> Result<(), Error> {
+    let rocket = load_rocket(rocket_path, catalog, system)?;
+    let body = match options.body {
+        Some(name) => system.body(name).ok_or_else(|| Error::UnknownBody(name.into()))?,
+        None => system.home,
+    };
+    let periapsis = options.periapsis.unwrap_or(body.low_orbit_altitude);
+    let apoapsis = options.apoapsis.unwrap_or(periapsis).max(periapsis);
+    let orbit = StartOrbit {
+        periapsis: body.radius + periapsis,
+        apoapsis: body.radius + apoapsis,
+    };
+    let stage = options.stage.unwrap_or(rocket.stages.len());
+    // The stage's last phase, once any boosters are gone
+    let phase = rocket.stage_numbers().into_iter().zip(rocket.stages())
+        .filter(|&(number, _)| number.stage == stage)
+        .last();
+    match phase {
+        Some((_, phase)) => print_finite_burn(&*phase, body, orbit, options.delta_v, options.max_loss),
+        None => println!("{}", Red.bold().paint(format!("This rocket has no stage {}", stage))),
+    }
+    Ok(())
+}
+
+pub fn engine(catalog: &Catalog, name: &str) -> Result<(), Error> {
+    let engine = catalog.engine(name)
+        .ok_or_else(|| Error::UnknownEngine(name.into()))?;
+    print_engine(engine);
+    Ok(())
+}
+
+fn load_rocket(path: &str, catalog: &Catalog, system: &System) -> Result<Rocket, Error> {
+    RocketDesign::load(path)?.build_for(catalog, system.home)
+}
@@ -0,0 +1,44 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde_json;
+use toml;
+
+use error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    pub fn for_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Some(Format::Toml),
+            Some("json") => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, source: &str) -> Result<T, Error> {
+        match self {
+            Format::Toml => Ok(toml::from_str(source)?),
+            Format::Json => Ok(serde_json::from_str(source)?),
+        }
+    }
+}
+
+/// Reads a TOML or JSON file, picking the format from its extension. Any
+/// error is tagged with the path of the file that caused it.
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let format = Format::for_path(path)
+        .ok_or_else(|| Error::UnsupportedFormat(path.to_path_buf()))?;
+    let mut source = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut source))
+        .map_err(|e| Error::from(e).in_file(path))?;
+    format.parse(&source).map_err(|e| e.in_file(path))
+}
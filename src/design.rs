@@ -0,0 +1,622 @@
+//! Rocket descriptions loaded from data files.
+//!
+//! Stages are listed from the bottom up. A stage with `boosters` is a
+//! `BoostedStage`, using the rest of the stage as its core. `boosters` is
+//! either a single table or an array of them, one per group of boosters, and
+//! groups with an `ignition_time` are air-lit that many seconds after the
+//! core. With `crossfeed`, the boosters feed the core's engines instead, and
+//! are dropped in the order they're listed (see `CrossfeedStage`).
+//!
+//! A stage with a `booster_section` is a stage-and-a-half `SustainerStage`.
+//! Its booster engines burn from the stage's own tanks until `staging_time`,
+//! and are dropped with `skirt_mass` (`ATLAS_DECOUPLER_MASS` by default).
+//! The stage's `dry_mass` covers the sustainer section only.
+//!
+//! `jettison` lists parts of a stage's `dry_mass`, like drop tanks or an
+//! interstage, which are dropped partway through its burn.
+//!
+//! A `fairing` and an `escape_system` sit on top of the rocket with the
+//! payload, and are dropped at a `jettison_time` after lift-off or once the
+//! ascent climbs to a `jettison_altitude`. Times count burn time only,
+//! ignoring coasts, and must be after lift-off. Each is added to the stage burning when it's dropped, as
+//! a jettison.
+//!
+//! Stages and boosters with a `tank` type (see `tanks`) have their tanks and
+//! engines added to their `dry_mass`, which then only covers the rest of the
+//! structure. Tanks are sized for the burns the engines actually make: their
+//! `burn_time` overrides, and a booster section's `staging_time`.
+//!
+//! Cryogenic propellants boil off during every coast before a stage lights,
+//! so `coast_days` adds up going up the stack. `insulation` (see
+//! `tanks::INSULATION`) slows the boiloff.
+//!
+//! ```toml
+//! payload_mass = 300.0
+//!
+//! [fairing]
+//! mass = 350.0
+//! jettison_altitude = 110000.0
+//!
+//! [[stages]]
+//! dry_mass = 5400.0
+//! engines = [{ name = "LR105-NA-3" }]
+//! verniers = "LR101-NA-3 Vernier"
+//!
+//! [stages.booster_section]
+//! engines = [{ name = "LR89-NA-3", count = 2 }]
+//! staging_time = 130.0
+//!
+//! [[stages]]
+//! dry_mass = 250.0
+//! tank = "Stainless"
+//! coast_days = 0.5
+//! engines = [{ name = "Bell 8048 (XLR81-BA-5, Agena A)", burn_time = 100.0 }]
+//!
+//! [[stages.jettison]]
+//! name = "Interstage"
+//! mass = 80.0
+//! time = 5.0
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use ascent::{self, AscentConfig};
+use bodies::Body;
+use catalog::Catalog;
+use data_file;
+use engines::Engine;
+use error::Error;
+use rocket::{PayloadTarget, Rocket};
+use stage::{BoostedStage, BoosterGroup, CrossfeedStage, Jettison, JettisonStage, JettisonTrigger, SimpleStage, Stage, SustainerStage, ATLAS_DECOUPLER_MASS};
+use tanks::{Insulation, TankType, NO_INSULATION};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RocketDesign {
+    #[serde(default)]
+    pub payload_mass: f64,
+    pub stages: Vec<StageDesign>,
+    pub fairing: Option<Component>,
+    pub escape_system: Option<Component>,
+}
+
+/// Mass carried on top of the rocket until it's dropped.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Component {
+    pub mass: f64,
+    /// Seconds after lift-off
+    pub jettison_time: Option<f64>,
+    /// In m, found by simulating the ascent
+    pub jettison_altitude: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StageDesign {
+    pub dry_mass: f64,
+    pub engines: Vec<EngineRef>,
+    pub verniers: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub boosters: Vec<BoosterDesign>,
+    #[serde(default)]
+    pub crossfeed: bool,
+    pub booster_section: Option<BoosterSectionDesign>,
+    pub tank: Option<String>,
+    /// Days between the previous stage's burnout (or rollout, for the
+    /// first stage) and this stage's ignition
+    #[serde(default)]
+    pub coast_days: f64,
+    pub insulation: Option<String>,
+    /// Sets of ullage motors, each of which settles propellant for one
+    /// ignition
+    #[serde(default)]
+    pub ullage_motors: u32,
+    /// Whether RCS can settle propellant before any ignition
+    #[serde(default)]
+    pub rcs: bool,
+    #[serde(default)]
+    pub jettison: Vec<JettisonDesign>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoosterDesign {
+    pub count: usize,
+    pub dry_mass: f64,
+    pub engines: Vec<EngineRef>,
+    pub tank: Option<String>,
+    /// Seconds after the core lights
+    #[serde(default)]
+    pub ignition_time: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoosterSectionDesign {
+    /// Burn times are ignored, the engines burn until staging
+    pub engines: Vec<EngineRef>,
+    /// Seconds after lift-off
+    pub staging_time: f64,
+    pub skirt_mass: Option<f64>,
+}
+
+/// Part of the stage's dry mass dropped during its burn, at either a
+/// `time` after ignition or once a `propellant_fraction` has burned.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JettisonDesign {
+    pub name: Option<String>,
+    pub mass: f64,
+    pub time: Option<f64>,
+    pub propellant_fraction: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EngineRef {
+    pub name: String,
+    #[serde(default = "one")]
+    pub count: usize,
+    pub burn_time: Option<f64>,
+}
+
+fn one() -> usize {
+    1
+}
+
+/// A single booster table or an array of them. Unlike an untagged enum, this
+/// keeps the errors from inside the tables, like unknown fields.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<BoosterDesign>, D::Error> {
+    struct OneOrMany;
+
+    impl<'de> Visitor<'de> for OneOrMany {
+        type Value = Vec<BoosterDesign>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a booster table or an array of them")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            BoosterDesign::deserialize(MapAccessDeserializer::new(map)).map(|boosters| vec![boosters])
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(SeqAccessDeserializer::new(seq))
+        }
+    }
+
+    deserializer.deserialize_any(OneOrMany)
+}
+
+impl RocketDesign {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        data_file::read(path.as_ref())
+    }
+
+    /// Fails if a component is dropped at an altitude, use `build_for`
+    /// instead.
+    pub fn build(&self, catalog: &Catalog) -> Result<Rocket, Error> {
+        self.with_components(catalog, None)?.build_stages(catalog)
+    }
+
+    /// Builds the rocket, flying its ascent over `body` to find when any
+    /// components are dropped.
+    pub fn build_for(&self, catalog: &Catalog, body: &Body) -> Result<Rocket, Error> {
+        self.with_components(catalog, Some(body))?.build_stages(catalog)
+    }
+
+    /// How much more payload the rocket could carry to `target` if its
+    /// fairing and escape system were dropped with the first stage instead.
+    /// Zero without either, and `None` if the rocket can't meet `target`.
+    pub fn component_penalty(&self, catalog: &Catalog, body: &Body, target: &PayloadTarget) -> Result<Option<f64>, Error> {
+        let first_burnout = self.without_components().build_stages(catalog)?
+            .stages.first()
+            .map(|s| total_burn_time(s))
+            .unwrap_or(0.0);
+        let components = self.components().into_iter()
+            .map(|(name, component)| (name, component.mass, first_burnout))
+            .collect::<Vec<_>>();
+        let max_payload = |design: RocketDesign| -> Result<Option<f64>, Error> {
+            // Components still aboard after the last burnout ride as payload
+            let carried = design.payload_mass - self.payload_mass;
+            Ok(design.build_stages(catalog)?.max_payload(target).map(|m| m.payload_mass - carried))
+        };
+        let early = max_payload(self.place_components(catalog, &components)?)?;
+        let actual = max_payload(self.with_components(catalog, Some(body))?)?;
+        Ok(early.and_then(|early| actual.map(|actual| early - actual)))
+    }
+
+    fn build_stages(&self, catalog: &Catalog) -> Result<Rocket, Error> {
+        let mut days = 0.0;
+        let stages = self.stages.iter().enumerate()
+            .map(|(i, stage)| {
+                days += stage.coast_days;
+                stage.build_after(days, catalog).map_err(|e| e.in_stage(i))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Rocket {
+            stages,
+            payload_mass: self.payload_mass,
+        })
+    }
+
+    fn components(&self) -> Vec<(&'static str, &Component)> {
+        let fairing = self.fairing.as_ref().map(|c| ("Fairing", c));
+        let escape_system = self.escape_system.as_ref().map(|c| ("Escape system", c));
+        fairing.into_iter().chain(escape_system).collect()
+    }
+
+    fn without_components(&self) -> Self {
+        RocketDesign { fairing: None, escape_system: None, ..self.clone() }
+    }
+
+    /// The design with its components moved into the stages as jettisons
+    fn with_components(&self, catalog: &Catalog, body: Option<&Body>) -> Result<Self, Error> {
+        if self.fairing.is_none() && self.escape_system.is_none() {
+            return Ok(self.clone());
+        }
+        let components = self.components().into_iter()
+            .map(|(name, component)| {
+                let time = match (component.jettison_time, component.jettison_altitude) {
+                    (Some(time), None) => time,
+                    (None, Some(altitude)) => {
+                        let body = body.ok_or(Error::JettisonAltitude)?;
+                        self.time_to_altitude(catalog, body, altitude)?
+                    }
+                    _ => return Err(Error::ComponentJettison),
+                };
+                if time.is_nan() || time <= 0.0 {
+                    return Err(Error::JettisonTime);
+                }
+                Ok((name, component.mass, time))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.place_components(catalog, &components)
+    }
+
+    /// Burn time after which the rocket reaches `altitude`, flying with its
+    /// components all the way
+    fn time_to_altitude(&self, catalog: &Catalog, body: &Body, altitude: f64) -> Result<f64, Error> {
+        let components = self.components().iter().map(|c| c.1.mass).sum::<f64>();
+        let rocket = self.without_components().build_stages(catalog)?
+            .with_payload_mass(self.payload_mass + components);
+        let config = AscentConfig { target_altitude: body.low_orbit_altitude, ..AscentConfig::default() };
+        Ok(ascent::time_to_altitude(&rocket, body, &config, altitude).unwrap_or(f64::INFINITY))
+    }
+
+    /// Adds each `(name, mass, time)` to the stage burning at that lift-off
+    /// time, or to the payload if every stage has burned out by then
+    fn place_components(&self, catalog: &Catalog, components: &[(&str, f64, f64)]) -> Result<Self, Error> {
+        let mut design = self.without_components();
+        let mut burnout = 0.0;
+        let burnouts = design.build_stages(catalog)?.stages.iter()
+            .map(|s| {
+                burnout += total_burn_time(s);
+                burnout
+            })
+            .collect::<Vec<_>>();
+
+        for &(name, mass, time) in components {
+            match burnouts.iter().position(|&b| time <= b) {
+                Some(i) => {
+                    let start = if i == 0 { 0.0 } else { burnouts[i - 1] };
+                    let stage = &mut design.stages[i];
+                    stage.dry_mass += mass;
+                    stage.jettison.push(JettisonDesign {
+                        name: Some(name.into()),
+                        mass,
+                        time: Some(time - start),
+                        propellant_fraction: None,
+                    });
+                }
+                None => design.payload_mass += mass,
+            }
+        }
+        Ok(design)
+    }
+}
+
+/// Burn time of every phase of the stage
+fn total_burn_time(stage: &dyn Stage) -> f64 {
+    let mut time = stage.burn_time();
+    let mut next = stage.next_stage();
+    while let Some(phase) = next {
+        time += phase.burn_time();
+        next = phase.next_stage();
+    }
+    time
+}
+
+impl StageDesign {
+    pub fn build(&self, catalog: &Catalog) -> Result<Box<dyn Stage>, Error> {
+        self.build_after(self.coast_days, catalog)
+    }
+
+    /// Builds the stage as it is after `days` of boiloff
+    pub fn build_after(&self, days: f64, catalog: &Catalog) -> Result<Box<dyn Stage>, Error> {
+        let stage = self.build_burn(days, catalog)?;
+        if self.jettison.is_empty() {
+            return Ok(stage);
+        }
+        let jettisons = self.jettison.iter()
+            .map(|j| {
+                let trigger = match (j.time, j.propellant_fraction) {
+                    (Some(time), None) => JettisonTrigger::Time(time),
+                    (None, Some(fraction)) => JettisonTrigger::PropellantFraction(fraction),
+                    _ => return Err(Error::JettisonTrigger),
+                };
+                Ok(Jettison { mass: j.mass, trigger })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Box::new(JettisonStage::new(stage, jettisons)?))
+    }
+
+    /// The stage without its jettisons
+    fn build_burn(&self, days: f64, catalog: &Catalog) -> Result<Box<dyn Stage>, Error> {
+        let insulation = match self.insulation {
+            Some(ref name) => Insulation::find(name)
+                .ok_or_else(|| Error::UnknownInsulation(name.clone()))?,
+            None => NO_INSULATION,
+        };
+
+        let mut core = simple_stage(self.dry_mass, &self.engines, catalog)?;
+        if let Some(ref vernier) = self.verniers {
+            core = core.with_verniers(find_engine(vernier, catalog)?);
+        }
+        add_tanks(&mut core, &self.tank)?;
+        core = core.after_boiloff(days, &insulation);
+
+        if let Some(ref section) = self.booster_section {
+            if !self.boosters.is_empty() {
+                return Err(Error::BoostersWithBoosterSection);
+            }
+            let mut boosters = simple_stage(0.0, &section.engines, catalog)?
+                .with_remaining_burn_time(section.staging_time);
+            if let Some(tank_type) = tank_type(&self.tank)? {
+                core.dry_mass += tank_type.mass_for(&boosters.engines);
+            }
+            // Propellant the boosters can't use stays in the shared tanks
+            boosters = boosters.after_boiloff(days, &insulation);
+            core.dry_mass += boosters.dry_mass;
+            return Ok(Box::new(SustainerStage {
+                sustainer: core,
+                staging_time: boosters.burn_time(),
+                booster_engines: boosters.engines,
+                skirt_mass: section.skirt_mass.unwrap_or(ATLAS_DECOUPLER_MASS),
+            }));
+        }
+        if self.boosters.is_empty() {
+            return Ok(Box::new(core));
+        }
+        let boosters = self.boosters.iter()
+            .map(|group| {
+                let mut booster = simple_stage(group.dry_mass, &group.engines, catalog)?;
+                add_tanks(&mut booster, &group.tank)?;
+                Ok(BoosterGroup {
+                    booster: booster.after_boiloff(days, &insulation),
+                    count: group.count,
+                    ignition_time: group.ignition_time,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if self.crossfeed {
+            if self.boosters.iter().any(|g| g.ignition_time != 0.0) {
+                return Err(Error::AirLitCrossfeed);
+            }
+            Ok(Box::new(CrossfeedStage { core, boosters }))
+        } else {
+            Ok(Box::new(BoostedStage { core, boosters }))
+        }
+    }
+}
+
+fn simple_stage(dry_mass: f64, engines: &[EngineRef], catalog: &Catalog) -> Result<SimpleStage, Error> {
+    if engines.is_empty() {
+        return Err(Error::NoEngines);
+    }
+
+    let mut result = Vec::new();
+    for engine_ref in engines {
+        let mut engine = find_engine(&engine_ref.name, catalog)?;
+        if let Some(burn_time) = engine_ref.burn_time {
+            engine = engine.with_burn_time(burn_time);
+        }
+        for _ in 0..engine_ref.count {
+            result.push(engine.clone());
+        }
+    }
+    Ok(SimpleStage { dry_mass, engines: result })
+}
+
+/// Adds the engines, and tanks for their burn times as built, to the stage's
+/// dry mass
+fn add_tanks(stage: &mut SimpleStage, tank: &Option<String>) -> Result<(), Error> {
+    if let Some(tank_type) = tank_type(tank)? {
+        stage.dry_mass += stage.engines.iter().map(|e| e.mass).sum::<f64>() +
+            tank_type.mass_for(&stage.engines);
+    }
+    Ok(())
+}
+
+fn tank_type(tank: &Option<String>) -> Result<Option<TankType>, Error> {
+    match *tank {
+        Some(ref name) => TankType::find(name)
+            .map(Some)
+            .ok_or_else(|| Error::UnknownTankType(name.clone())),
+        None => Ok(None),
+    }
+}
+
+fn find_engine(name: &str, catalog: &Catalog) -> Result<Engine, Error> {
+    catalog.engine(name).cloned()
+        .ok_or_else(|| Error::UnknownEngine(name.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bodies::rss::EARTH;
+    use engines::BELL_8048;
+    use serde_json;
+    use stage::GRAVITY;
+    use toml;
+
+    fn agena(burn_time: &str) -> StageDesign {
+        toml::from_str(&format!(r#"
+            dry_mass = 250.0
+            tank = "Stainless"
+            engines = [{{ name = "Bell 8048 (XLR81-BA-5, Agena A)"{} }}]
+        "#, burn_time)).unwrap()
+    }
+
+    #[test]
+    fn tanks_follow_burn_time_overrides() {
+        let catalog = Catalog::builtin();
+        let catalog_burn = agena("").build(&catalog).unwrap();
+        let short_burn = agena(", burn_time = 100.0").build(&catalog).unwrap();
+
+        // 250 kg structure, 132 kg engine, and 0.1 kg of tank per litre of
+        // 8.8115 + 10.7262 l/s
+        assert!((catalog_burn.dry_mass() - (382.0 + 0.1 * 19.5377 * 120.0)).abs() < 1e-6);
+        assert!((short_burn.dry_mass() - (382.0 + 0.1 * 19.5377 * 100.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn booster_tanks_follow_burn_time_overrides() {
+        let design: StageDesign = toml::from_str(r#"
+            dry_mass = 1000.0
+            engines = [{ name = "LR105-NA-5" }]
+
+            [[boosters]]
+            count = 2
+            dry_mass = 100.0
+            tank = "Stainless"
+            engines = [{ name = "Bell 8048 (XLR81-BA-5, Agena A)", burn_time = 50.0 }]
+        "#).unwrap();
+        let stage = design.build(&Catalog::builtin()).unwrap();
+        let propellant = stage.all_engines().iter().map(Engine::propellant_mass_for_full_burn).sum::<f64>();
+
+        // The core has no tank type, so only its given dry mass
+        let boosters = 2.0 * (232.0 + 0.1 * 19.5377 * 50.0);
+        assert!((stage.wet_mass() - propellant - (1000.0 + boosters)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn boosters_are_one_table_or_many() {
+        let one: StageDesign = toml::from_str(r#"
+            dry_mass = 1000.0
+            engines = [{ name = "LR105-NA-5" }]
+            boosters = { count = 2, dry_mass = 100.0, engines = [{ name = "Castor 1" }] }
+        "#).unwrap();
+        assert_eq!(1, one.boosters.len());
+
+        let many: StageDesign = toml::from_str(r#"
+            dry_mass = 1000.0
+            engines = [{ name = "LR105-NA-5" }]
+
+            [[boosters]]
+            count = 2
+            dry_mass = 100.0
+            engines = [{ name = "Castor 1" }]
+
+            [[boosters]]
+            count = 1
+            dry_mass = 100.0
+            engines = [{ name = "Castor 1" }]
+            ignition_time = 30.0
+        "#).unwrap();
+        assert_eq!(vec![2, 1], many.boosters.iter().map(|b| b.count).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn booster_errors_reach_the_user() {
+        let source = r#"
+            dry_mass = 1000.0
+            engines = [{ name = "LR105-NA-5" }]
+            boosters = { count = 2, dry_mass = 100.0, engines = [{ name = "Castor 1" }], ignition = 5.0 }
+        "#;
+        let error = toml::from_str::<StageDesign>(source).unwrap_err().to_string();
+        assert!(error.contains("unknown field `ignition`"), "{}", error);
+
+        let json = r#"{ "dry_mass": 1000.0, "engines": [{ "name": "LR105-NA-5" }],
+            "boosters": [{ "count": 2, "dry_mass": 100.0, "engines": [{ "name": "Castor 1" }], "ignition": 5.0 }] }"#;
+        let error = serde_json::from_str::<StageDesign>(json).unwrap_err().to_string();
+        assert!(error.contains("unknown field `ignition`"), "{}", error);
+    }
+
+    /// Two Agena A stages, 1000 kg and 500 kg dry, with a 200 kg fairing
+    fn two_agenas(jettison_time: f64) -> RocketDesign {
+        toml::from_str(&format!(r#"
+            payload_mass = 100.0
+            fairing = {{ mass = 200.0, jettison_time = {:?} }}
+
+            [[stages]]
+            dry_mass = 1000.0
+            engines = [{{ name = "Bell 8048 (XLR81-BA-5, Agena A)" }}]
+
+            [[stages]]
+            dry_mass = 500.0
+            engines = [{{ name = "Bell 8048 (XLR81-BA-5, Agena A)" }}]
+        "#, jettison_time)).unwrap()
+    }
+
+    /// Delta-v of a burn from `wet` to `dry` kg on one Agena A engine
+    fn agena_delta_v(wet: f64, dry: f64) -> f64 {
+        BELL_8048.isp * GRAVITY * (wet / dry).ln()
+    }
+
+    #[test]
+    fn components_ride_with_the_stage_burning_when_dropped() {
+        let flow = BELL_8048.propellant_mass_per_second();
+        let propellant = flow * 120.0;
+        let second_stage = 500.0 + propellant;
+        let rocket = two_agenas(150.0).build(&Catalog::builtin()).unwrap();
+
+        // Dropped 30 s into the second stage's burn
+        let delta_v = agena_delta_v(1000.0 + propellant + second_stage + 300.0, 1000.0 + second_stage + 300.0)
+            + agena_delta_v(second_stage + 300.0, second_stage + 300.0 - flow * 30.0)
+            + agena_delta_v(second_stage + 100.0 - flow * 30.0, 600.0);
+        assert!((rocket.delta_v() - delta_v).abs() < 1e-6, "{} {}", rocket.delta_v(), delta_v);
+    }
+
+    #[test]
+    fn components_must_drop_after_lift_off() {
+        for &time in &[0.0, -10.0] {
+            match two_agenas(time).build(&Catalog::builtin()) {
+                Err(Error::JettisonTime) => {}
+                other => panic!("{}: {:?}", time, other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn component_penalty_is_the_payload_lost_carrying_them() {
+        let catalog = Catalog::builtin();
+        let target = PayloadTarget { delta_v: 5000.0, tolerance: 0.01, ..PayloadTarget::default() };
+        let propellant = BELL_8048.propellant_mass_for_full_burn();
+        let second_stage = 500.0 + propellant;
+        let with_fairing = |payload: f64, fairing_on_second_stage: f64| {
+            agena_delta_v(1000.0 + propellant + second_stage + payload + 200.0, 1000.0 + second_stage + payload + 200.0)
+                + agena_delta_v(second_stage + payload + fairing_on_second_stage, 500.0 + payload + fairing_on_second_stage)
+        };
+
+        // Carried to orbit, against dropped with the first stage
+        let design = two_agenas(1000.0);
+        let penalty = design.component_penalty(&catalog, &EARTH, &target).unwrap().unwrap();
+        // The fairing counts as payload to `Rocket::max_payload`
+        let carried = design.build(&catalog).unwrap().max_payload(&target).unwrap().payload_mass - 200.0;
+        assert!((with_fairing(carried, 200.0) - 5000.0).abs() < 0.1, "{}", with_fairing(carried, 200.0));
+        assert!((with_fairing(carried + penalty, 0.0) - 5000.0).abs() < 0.1, "{}", with_fairing(carried + penalty, 0.0));
+        assert!(penalty > 0.0 && penalty < 200.0, "{}", penalty);
+
+        // Already dropped at the first stage's burnout
+        let penalty = two_agenas(120.0).component_penalty(&catalog, &EARTH, &target).unwrap().unwrap();
+        assert!(penalty.abs() <= target.tolerance, "{}", penalty);
+    }
+}
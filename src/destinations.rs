@@ -0,0 +1,357 @@
+//! Delta-v required to move between locations in a planetary system.
+//!
+//! A `DeltaVMap` is a directed graph of locations (surfaces, low orbits and
+//! transfer orbits) whose edges carry the delta-v of the burn between them.
+//! Edges ending in an atmosphere are flagged as aerobraking, and cost nothing
+//! when routing with aerobraking allowed.
+
+use std::f64;
+
+use bodies::{hohmann, Body, System};
+use error::Error;
+
+/// A destination is only considered safely reachable when the rocket has
+/// this much more delta-v than required.
+pub const SAFETY_MARGIN: f64 = 1.05;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Destination {
+    pub name: String,
+    /// Delta-v required from the launch pad
+    pub delta_v: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub delta_v: f64,
+    /// Whether the burn can be replaced by aerobraking
+    pub aerobrake: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeltaVMap {
+    locations: Vec<String>,
+    edges: Vec<Edge>,
+}
+
+/// A path through a `DeltaVMap`, with the delta-v of each leg.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub locations: Vec<String>,
+    /// One entry per leg, so one shorter than `locations`
+    pub legs: Vec<f64>,
+}
+
+impl Route {
+    pub fn delta_v(&self) -> f64 {
+        self.legs.iter().sum()
+    }
+}
+
+impl DeltaVMap {
+    pub fn new() -> Self {
+        DeltaVMap::default()
+    }
+
+    /// The launch site and low orbit of the system's home body, its
+    /// synchronous orbits, and transfers to, orbits around and landings on
+    /// its moons, the other planets and their moons. Landings on bodies with
+    /// an atmosphere are one way, since getting back up depends on the
+    /// lander.
+    pub fn for_system(system: &System) -> Self {
+        let mut map = DeltaVMap::new();
+        let home = system.home;
+        let surface = map.add_location(&surface(home));
+        let low_orbit = map.add_location(&low_orbit(home));
+        map.add_edge(surface, low_orbit, system.launch_delta_v, false);
+        map.add_edge(low_orbit, surface, home.landing_delta_v(), home.atmosphere.is_some());
+
+        let (to_transfer, circularize) = hohmann(
+            home.gravitational_parameter,
+            home.low_orbit_radius(),
+            home.synchronous_orbit_radius(),
+        );
+        let gto = map.add_location("GTO");
+        let geo = map.add_location("GEO");
+        map.add_route(low_orbit, gto, to_transfer, home.atmosphere.is_some());
+        map.add_route(gto, geo, circularize, false);
+
+        map.add_moons(system, home);
+        if let Some(star) = home.parent() {
+            for planet in system.moons_of(star).into_iter().filter(|&p| p != home) {
+                let (departure_excess, arrival_excess) = hohmann(
+                    star.gravitational_parameter,
+                    home.semi_major_axis(),
+                    planet.semi_major_axis(),
+                );
+                let departure = home.departure_delta_v(home.low_orbit_radius(), departure_excess);
+                map.add_body(planet, low_orbit, departure, arrival_excess, home);
+                map.add_moons(system, planet);
+            }
+        }
+
+        map
+    }
+
+    pub fn locations(&self) -> &[String] {
+        &self.locations
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Looks up a location by name, ignoring case
+    pub fn location(&self, name: &str) -> Option<usize> {
+        self.locations.iter().position(|l| l.eq_ignore_ascii_case(name))
+    }
+
+    /// Adds a location, or returns the existing one with the same name
+    pub fn add_location(&mut self, name: &str) -> usize {
+        match self.location(name) {
+            Some(i) => i,
+            None => {
+                self.locations.push(name.into());
+                self.locations.len() - 1
+            }
+        }
+    }
+
+    /// Adds a one way edge
+    pub fn add_edge(&mut self, from: usize, to: usize, delta_v: f64, aerobrake: bool) {
+        self.edges.push(Edge { from, to, delta_v, aerobrake });
+    }
+
+    /// Adds edges both ways with the same delta-v. Only the way back to
+    /// `from` can be aerobraked.
+    pub fn add_route(&mut self, from: usize, to: usize, delta_v: f64, aerobrake_back: bool) {
+        self.add_edge(from, to, delta_v, false);
+        self.add_edge(to, from, delta_v, aerobrake_back);
+    }
+
+    /// The cheapest route between two locations
+    pub fn shortest_path(&self, from: usize, to: usize, aerobrake: bool) -> Option<Route> {
+        let (cost, previous) = self.dijkstra(from, aerobrake);
+        if cost[to].is_infinite() {
+            return None;
+        }
+
+        let mut path = vec![to];
+        while let Some(edge) = previous[*path.last().unwrap()] {
+            path.push(self.edges[edge].from);
+        }
+        path.reverse();
+
+        Some(Route {
+            locations: path.iter().map(|&i| self.locations[i].clone()).collect(),
+            legs: path.windows(2).map(|w| cost[w[1]] - cost[w[0]]).collect(),
+        })
+    }
+
+    /// The cheapest route visiting each of `stops` in order
+    pub fn itinerary<S: AsRef<str>>(&self, stops: &[S], aerobrake: bool) -> Result<Route, Error> {
+        let indices = stops.iter()
+            .map(|s| self.location(s.as_ref())
+                .ok_or_else(|| Error::UnknownDestination(s.as_ref().into())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut route = Route {
+            locations: indices.first().map(|&i| self.locations[i].clone()).into_iter().collect(),
+            legs: Vec::new(),
+        };
+        for pair in indices.windows(2) {
+            let leg = self.shortest_path(pair[0], pair[1], aerobrake)
+                .ok_or_else(|| Error::NoRoute {
+                    from: self.locations[pair[0]].clone(),
+                    to: self.locations[pair[1]].clone(),
+                })?;
+            route.locations.extend(leg.locations.into_iter().skip(1));
+            route.legs.extend(leg.legs);
+        }
+        Ok(route)
+    }
+
+    /// Every location reachable from `from`, with the delta-v of the cheapest
+    /// route there
+    pub fn destinations_from(&self, from: usize, aerobrake: bool) -> Vec<Destination> {
+        let (cost, _) = self.dijkstra(from, aerobrake);
+        self.locations.iter().zip(cost)
+            .enumerate()
+            .filter(|&(i, (_, cost))| i != from && cost.is_finite())
+            .map(|(_, (name, delta_v))| Destination { name: name.clone(), delta_v })
+            .collect()
+    }
+
+    fn dijkstra(&self, from: usize, aerobrake: bool) -> (Vec<f64>, Vec<Option<usize>>) {
+        let mut cost = vec![f64::INFINITY; self.locations.len()];
+        let mut previous = vec![None; self.locations.len()];
+        let mut done = vec![false; self.locations.len()];
+        cost[from] = 0.0;
+
+        // The maps are small enough that a linear scan beats a heap
+        while let Some(current) = (0..cost.len())
+            .filter(|&i| !done[i] && cost[i].is_finite())
+            .min_by(|&a, &b| cost[a].partial_cmp(&cost[b]).unwrap())
+        {
+            done[current] = true;
+            for (i, edge) in self.edges.iter().enumerate().filter(|&(_, e)| e.from == current) {
+                let delta_v = if aerobrake && edge.aerobrake { 0.0 } else { edge.delta_v };
+                if cost[current] + delta_v < cost[edge.to] {
+                    cost[edge.to] = cost[current] + delta_v;
+                    previous[edge.to] = Some(i);
+                }
+            }
+        }
+
+        (cost, previous)
+    }
+
+    fn add_moons(&mut self, system: &System, parent: &Body) {
+        let parking_orbit = self.add_location(&low_orbit(parent));
+        for moon in system.moons_of(parent) {
+            let (injection, excess_speed) = hohmann(
+                parent.gravitational_parameter,
+                parent.low_orbit_radius(),
+                moon.semi_major_axis(),
+            );
+            self.add_body(moon, parking_orbit, injection, excess_speed, parent);
+        }
+    }
+
+    /// Adds a transfer to `body` from `parking_orbit` around `origin`, and
+    /// the low orbit and surface of `body`
+    fn add_body(&mut self, body: &Body, parking_orbit: usize, injection: f64, excess_speed: f64, origin: &Body) {
+        let transfer = self.add_location(body.name);
+        let low_orbit = self.add_location(&low_orbit(body));
+        let surface = self.add_location(&surface(body));
+        let atmosphere = body.atmosphere.is_some();
+
+        self.add_route(parking_orbit, transfer, injection, origin.atmosphere.is_some());
+        self.add_edge(transfer, low_orbit, body.capture_delta_v(excess_speed), atmosphere);
+        self.add_edge(low_orbit, transfer, body.capture_delta_v(excess_speed), false);
+        self.add_edge(low_orbit, surface, body.landing_delta_v(), atmosphere);
+        if !atmosphere {
+            self.add_edge(surface, low_orbit, body.landing_delta_v(), false);
+        }
+    }
+}
+
+pub fn low_orbit(body: &Body) -> String {
+    format!("Low {} Orbit", body.name)
+}
+
+pub fn surface(body: &Body) -> String {
+    format!("{} Surface", body.name)
+}
+
+/// Every location reachable from the launch pad of the system's home body
+pub fn destinations(system: &System) -> Vec<Destination> {
+    let map = DeltaVMap::for_system(system);
+    let launch_site = map.location(&surface(system.home)).expect("added by for_system");
+    map.destinations_from(launch_site, false)
+}
+
+/// Destinations to quote payloads for: `orbit` for the launch itself, then
+/// everything past the parking orbit with 1.5% to spare
+pub fn payload_targets(system: &System) -> Vec<(String, f64)> {
+    let mut targets = vec![("orbit".to_string(), system.launch_delta_v)];
+    let parking_orbit = low_orbit(system.home);
+    targets.extend(destinations(system).into_iter().filter(|d| d.name != parking_orbit).map(|d| (d.name, d.delta_v * 1.015)));
+    targets
+}
+
+/// The entry of `payload_targets` called `name`, ignoring case
+pub fn payload_target(system: &System, name: &str) -> Result<(String, f64), Error> {
+    payload_targets(system).into_iter()
+        .find(|entry| entry.0.eq_ignore_ascii_case(name))
+        .ok_or_else(|| Error::UnknownDestination(name.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bodies::rss;
+
+    /// A to C directly for 250 m/s, or through B for 200 m/s, with an
+    /// aerobraking return from C and a location D that can't be reached
+    fn map() -> DeltaVMap {
+        let mut map = DeltaVMap::new();
+        let a = map.add_location("A");
+        let b = map.add_location("B");
+        let c = map.add_location("C");
+        map.add_location("D");
+        map.add_edge(a, b, 100.0, false);
+        map.add_edge(b, c, 100.0, false);
+        map.add_edge(a, c, 250.0, false);
+        map.add_edge(c, a, 500.0, true);
+        map
+    }
+
+    #[test]
+    fn takes_the_cheapest_path() {
+        let route = map().shortest_path(0, 2, false).unwrap();
+        assert_eq!(vec!["A", "B", "C"], route.locations);
+        assert_eq!(vec![100.0, 100.0], route.legs);
+        assert_eq!(None, map().shortest_path(0, 3, false));
+    }
+
+    #[test]
+    fn itineraries_join_their_legs() {
+        let route = map().itinerary(&["a", "C", "A"], false).unwrap();
+        assert_eq!(vec!["A", "B", "C", "A"], route.locations);
+        assert_eq!(vec![100.0, 100.0, 500.0], route.legs);
+
+        let aerobraked = map().itinerary(&["A", "C", "A"], true).unwrap();
+        assert_eq!(200.0, aerobraked.delta_v());
+
+        match map().itinerary(&["A", "D"], false) {
+            Err(Error::NoRoute { ref from, ref to }) => assert_eq!(("A", "D"), (&from[..], &to[..])),
+            other => panic!("{:?}", other),
+        }
+        match map().itinerary(&["A", "E"], false) {
+            Err(Error::UnknownDestination(ref name)) => assert_eq!("E", name),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn destinations_skip_the_start_and_unreachable_locations() {
+        let destinations = map().destinations_from(1, true);
+        let expected = vec![
+            Destination { name: "A".into(), delta_v: 100.0 },
+            Destination { name: "C".into(), delta_v: 100.0 },
+        ];
+        assert_eq!(expected, destinations);
+    }
+
+    #[test]
+    fn system_maps_reach_geo_through_gto() {
+        let system = &rss::SYSTEM;
+        let map = DeltaVMap::for_system(system);
+        let route = map.itinerary(&[surface(system.home), "GEO".into()], false).unwrap();
+        let (to_transfer, circularize) = hohmann(
+            system.home.gravitational_parameter,
+            system.home.low_orbit_radius(),
+            system.home.synchronous_orbit_radius(),
+        );
+        assert_eq!(vec![surface(system.home), low_orbit(system.home), "GTO".into(), "GEO".into()], route.locations);
+        assert_eq!(vec![system.launch_delta_v, to_transfer, circularize], route.legs);
+    }
+
+    #[test]
+    fn payload_targets_skip_the_parking_orbit() {
+        let targets = payload_targets(&rss::SYSTEM);
+        assert_eq!(("orbit".to_string(), rss::SYSTEM.launch_delta_v), targets[0]);
+        assert!(targets.iter().all(|t| t.0 != low_orbit(&rss::EARTH)));
+
+        let (name, delta_v) = payload_target(&rss::SYSTEM, "gto").unwrap();
+        let gto = destinations(&rss::SYSTEM).into_iter().find(|d| d.name == "GTO").unwrap();
+        assert_eq!(("GTO", gto.delta_v * 1.015), (&name[..], delta_v));
+        match payload_target(&rss::SYSTEM, "Nowhere") {
+            Err(Error::UnknownDestination(ref name)) => assert_eq!("Nowhere", name),
+            other => panic!("{:?}", other),
+        }
+    }
+}
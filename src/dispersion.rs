@@ -0,0 +1,260 @@
+//! Monte Carlo dispersion analysis.
+//!
+//! Each sample rebuilds the rocket from its design with engine Isp and
+//! thrust, stage dry masses and propellant residuals drawn from normal
+//! distributions. Every copy of an engine in a sample gets the same Isp and
+//! thrust. Residuals are propellant left in the tanks at burnout, which
+//! shortens the burn and stays behind as dead weight.
+
+use std::f64::consts::PI;
+use std::slice;
+
+use bodies::Body;
+use catalog::Catalog;
+use design::{EngineRef, RocketDesign};
+use engines::Engine;
+use error::Error;
+use rocket::PayloadTarget;
+use tanks::TankType;
+
+/// Standard deviations of each perturbation, as fractions of the nominal
+/// value. Residuals are a fraction of the stage's propellant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dispersion {
+    pub isp: f64,
+    pub thrust: f64,
+    pub residuals: f64,
+    pub dry_mass: f64,
+    pub samples: usize,
+    pub seed: u64,
+}
+
+impl Default for Dispersion {
+    fn default() -> Self {
+        Dispersion {
+            isp: 0.01,
+            thrust: 0.02,
+            residuals: 0.01,
+            dry_mass: 0.03,
+            samples: 2000,
+            seed: 1,
+        }
+    }
+}
+
+/// Sorted results of every sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Samples {
+    pub delta_v: Vec<f64>,
+    /// Max payload for the target given to `run`, zero when a sample can't
+    /// meet it at all
+    pub payload: Vec<f64>,
+}
+
+impl Samples {
+    /// The value below which `percent` of the samples fall
+    pub fn percentile(values: &[f64], percent: f64) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        let index = (percent / 100.0 * (values.len() - 1) as f64).round() as usize;
+        values[index.min(values.len() - 1)]
+    }
+
+    /// Share of samples with at least `delta_v`
+    pub fn probability_of_delta_v(&self, delta_v: f64) -> f64 {
+        let reaching = self.delta_v.iter().filter(|&&dv| dv >= delta_v).count();
+        reaching as f64 / self.delta_v.len().max(1) as f64
+    }
+}
+
+/// Samples `design` flying from `body`, which is only used to find when any
+/// fairing or escape system is dropped
+pub fn run(design: &RocketDesign, catalog: &Catalog, body: &Body, dispersion: &Dispersion, target: &PayloadTarget) -> Result<Samples, Error> {
+    let mut random = Random::new(dispersion.seed);
+    let mut samples = Samples {
+        delta_v: Vec::with_capacity(dispersion.samples),
+        payload: Vec::with_capacity(dispersion.samples),
+    };
+
+    for _ in 0..dispersion.samples {
+        let (design, catalog) = sample(design, catalog, dispersion, &mut random)?;
+        let rocket = design.build_for(&catalog, body)?;
+        samples.delta_v.push(rocket.delta_v());
+        samples.payload.push(rocket.max_payload(target).map(|m| m.payload_mass).unwrap_or(0.0));
+    }
+
+    samples.delta_v.sort_by(|a, b| a.partial_cmp(b).expect("We should never get NaN here"));
+    samples.payload.sort_by(|a, b| a.partial_cmp(b).expect("We should never get NaN here"));
+    Ok(samples)
+}
+
+/// A perturbed copy of the design, and a catalog holding perturbed copies of
+/// the engines it uses
+fn sample(design: &RocketDesign, catalog: &Catalog, dispersion: &Dispersion, random: &mut Random) -> Result<(RocketDesign, Catalog), Error> {
+    let mut design = design.clone();
+    let mut engines = Catalog::default();
+
+    for (i, stage) in design.stages.iter_mut().enumerate() {
+        let names = stage.engines.iter().map(|e| &e.name)
+            .chain(&stage.verniers)
+            .chain(stage.boosters.iter().flat_map(|b| b.engines.iter().map(|e| &e.name)))
+            .chain(stage.booster_section.iter().flat_map(|b| b.engines.iter().map(|e| &e.name)));
+        for name in names {
+            if engines.engine(name).is_none() {
+                let mut engine = catalog.engine(name).cloned()
+                    .ok_or_else(|| Error::UnknownEngine(name.clone()).in_stage(i))?;
+                let isp_factor = 1.0 + random.normal() * dispersion.isp;
+                engine.isp *= isp_factor;
+                engine.sea_level_isp *= isp_factor;
+                engine.thrust *= 1.0 + random.normal() * dispersion.thrust;
+                engines.insert_engine(engine);
+            }
+        }
+
+        stage.dry_mass *= 1.0 + random.normal() * dispersion.dry_mass;
+        let residuals = (random.normal() * dispersion.residuals).abs();
+        // Verniers burn as long as the first engine, so they're cut short too
+        let vernier_burn = stage.verniers.as_ref()
+            .and_then(|v| engines.engine(v))
+            .and_then(|v| Some((v, burn_time(stage.engines.first()?, &engines)?)));
+        if let Some((vernier, burn_time)) = vernier_burn {
+            stage.dry_mass += 2.0 * residuals * left_behind(vernier, burn_time, &stage.tank);
+        }
+        stage.dry_mass += leave_residuals(&mut stage.engines, residuals, &stage.tank, &engines);
+
+        for boosters in &mut stage.boosters {
+            boosters.dry_mass *= 1.0 + random.normal() * dispersion.dry_mass;
+            let residuals = (random.normal() * dispersion.residuals).abs();
+            boosters.dry_mass += leave_residuals(&mut boosters.engines, residuals, &boosters.tank, &engines);
+        }
+    }
+
+    Ok((design, engines))
+}
+
+/// Shortens the engines' burns by `residuals`, returning the mass left
+/// behind
+fn leave_residuals(engines: &mut [EngineRef], residuals: f64, tank: &Option<String>, catalog: &Catalog) -> f64 {
+    let mut left = 0.0;
+    for engine_ref in engines {
+        if let (Some(engine), Some(burn_time)) = (catalog.engine(&engine_ref.name), burn_time(engine_ref, catalog)) {
+            left += residuals * left_behind(engine, burn_time, tank) * engine_ref.count as f64;
+            engine_ref.burn_time = Some(burn_time * (1.0 - residuals));
+        }
+    }
+    left
+}
+
+/// Mass of the propellant for a burn, plus the tanks holding it when the
+/// design sizes its tanks from the burn time
+fn left_behind(engine: &Engine, burn_time: f64, tank: &Option<String>) -> f64 {
+    let engine = engine.with_burn_time(burn_time);
+    let tank_mass = tank.as_ref()
+        .and_then(|name| TankType::find(name))
+        .map(|tank_type| tank_type.mass_for(slice::from_ref(&engine)))
+        .unwrap_or(0.0);
+    engine.propellant_mass_for_full_burn() + tank_mass
+}
+
+fn burn_time(engine_ref: &EngineRef, catalog: &Catalog) -> Option<f64> {
+    engine_ref.burn_time.or_else(|| catalog.engine(&engine_ref.name).map(|e| e.burn_time))
+}
+
+/// xorshift64*, which is plenty for sampling and keeps runs reproducible
+/// from a seed
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        // Zero is the one state xorshift can't leave
+        Random(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in (0, 1]
+    fn uniform(&mut self) -> f64 {
+        ((self.next() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal, by the Box-Muller transform
+    fn normal(&mut self) -> f64 {
+        (-2.0 * self.uniform().ln()).sqrt() * (2.0 * PI * self.uniform()).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bodies::rss::EARTH;
+    use toml;
+
+    fn atlas_agena() -> RocketDesign {
+        toml::from_str(include_str!("../examples/atlas_agena.toml")).unwrap()
+    }
+
+    #[test]
+    fn xorshift_follows_the_reference_sequence() {
+        let mut random = Random::new(1);
+        let values = (0..3).map(|_| random.next()).collect::<Vec<_>>();
+        assert_eq!(vec![0x47e4_ce4b_896c_dd1d, 0xabcf_a6a8_e079_651d, 0xb9d1_0d8f_eb73_1f57], values);
+
+        // A zero seed would be stuck at zero
+        assert_eq!(Random::new(1).next(), Random::new(0).next());
+    }
+
+    #[test]
+    fn normals_have_unit_spread() {
+        let mut random = Random::new(42);
+        let values = (0..100_000).map(|_| random.normal()).collect::<Vec<_>>();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        let within_one = values.iter().filter(|v| v.abs() < 1.0).count() as f64 / values.len() as f64;
+
+        assert!(mean.abs() < 0.01, "{}", mean);
+        assert!((variance - 1.0).abs() < 0.02, "{}", variance);
+        assert!((within_one - 0.6827).abs() < 0.01, "{}", within_one);
+    }
+
+    #[test]
+    fn success_rate_counts_samples_reaching_the_delta_v() {
+        let samples = Samples { delta_v: vec![9000.0, 9400.0, 9800.0, 10_200.0], payload: vec![] };
+        assert_eq!(1.0, samples.probability_of_delta_v(9000.0));
+        assert_eq!(0.5, samples.probability_of_delta_v(9500.0));
+        assert_eq!(0.0, samples.probability_of_delta_v(11_000.0));
+        assert_eq!(0.0, Samples { delta_v: vec![], payload: vec![] }.probability_of_delta_v(0.0));
+
+        assert_eq!(9000.0, Samples::percentile(&samples.delta_v, 5.0));
+        assert_eq!(9800.0, Samples::percentile(&samples.delta_v, 50.0));
+        assert_eq!(10_200.0, Samples::percentile(&samples.delta_v, 95.0));
+    }
+
+    #[test]
+    fn runs_repeat_for_a_seed() {
+        let catalog = Catalog::builtin();
+        let target = PayloadTarget { delta_v: 9400.0, tolerance: 10.0, ..PayloadTarget::default() };
+        let dispersion = Dispersion { samples: 20, ..Dispersion::default() };
+        let first = run(&atlas_agena(), &catalog, &EARTH, &dispersion, &target).unwrap();
+        let again = run(&atlas_agena(), &catalog, &EARTH, &dispersion, &target).unwrap();
+        let other = run(&atlas_agena(), &catalog, &EARTH, &Dispersion { seed: 2, ..dispersion }, &target).unwrap();
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert!(first.delta_v.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn no_dispersion_flies_the_nominal_rocket() {
+        let catalog = Catalog::builtin();
+        let nominal = atlas_agena().build_for(&catalog, &EARTH).unwrap().delta_v();
+        let dispersion = Dispersion { isp: 0.0, thrust: 0.0, residuals: 0.0, dry_mass: 0.0, samples: 3, seed: 1 };
+        let samples = run(&atlas_agena(), &catalog, &EARTH, &dispersion, &PayloadTarget::default()).unwrap();
+        assert!(samples.delta_v.iter().all(|&dv| (dv - nominal).abs() < 1e-6), "{:?} {}", samples.delta_v, nominal);
+    }
+}
@@ -1,25 +1,71 @@
+use std::borrow::Cow;
+
 pub use super::fuels::*;
 
-#[derive(Debug, Clone, Copy)]
+/// Pressure at sea level, in atmospheres
+pub const SEA_LEVEL: f64 = 1.0;
+/// Pressure in a vacuum, in atmospheres
+pub const VACUUM: f64 = 0.0;
+/// How much faster engines fail past their rated burn time
+pub const OVERBURN_PENALTY: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Engine {
-    pub name: &'static str,
-    pub fuel_consumption: &'static [(Fuel, f64)],
+    pub name: Cow<'static, str>,
+    pub fuel_consumption: Cow<'static, [(Fuel, f64)]>,
+    /// Vacuum Isp, in seconds
     pub isp: f64,
+    /// Sea level Isp, in seconds
+    pub sea_level_isp: f64,
+    /// Vacuum thrust, in kN
     pub thrust: f64,
     pub mass: f64,
     pub burn_time: f64,
+    /// Price of one engine, in funds. Zero when unknown.
+    pub cost: f64,
+    /// How many times the engine can be lit. `None` if unlimited.
+    pub ignitions: Option<u32>,
+    /// Whether the propellant has to be settled before ignition
+    pub ullage: bool,
+    /// Lowest throttle setting, as a fraction of `thrust`. 1 if the engine
+    /// can't throttle.
+    pub min_throttle: f64,
+    /// Highest throttle setting, as a fraction of `thrust`
+    pub max_throttle: f64,
+    /// Burn time the engine is designed for. Burning longer makes failure
+    /// much more likely.
+    pub rated_burn_time: f64,
+    /// Chance that each ignition succeeds
+    pub ignition_reliability: f64,
+    /// Chance of surviving a full rated burn once lit
+    pub cycle_reliability: f64,
+    /// Whether the engine only comes as a pair of verniers on a stage, for
+    /// roll control and trimming the final velocity
+    pub vernier: bool,
 }
 
 impl Engine {
+    /// Isp at the given ambient pressure, in atmospheres. Interpolates
+    /// linearly between the vacuum and sea level figures.
+    pub fn isp_at(&self, pressure: f64) -> f64 {
+        (self.isp - (self.isp - self.sea_level_isp) * pressure).max(0.0)
+    }
+
+    /// Thrust at the given ambient pressure. Mass flow doesn't change with
+    /// pressure, so thrust scales with Isp.
+    pub fn thrust_at(&self, pressure: f64) -> f64 {
+        self.thrust * self.isp_at(pressure) / self.isp
+    }
+
     pub fn propellant_mass_per_second(&self) -> f64 {
         self.fuel_consumption.iter()
-            .map(|&(fuel, rate)| fuel.density * rate)
+            .map(|&(ref fuel, rate)| fuel.density * rate)
             .sum()
     }
 
     pub fn propellants_required(&self) -> Vec<(Fuel, f64)> {
         self.fuel_consumption.iter()
-            .map(|&(fuel, rate)| (fuel, rate * self.burn_time))
+            .map(|&(ref fuel, rate)| (fuel.clone(), rate * self.burn_time))
             .collect()
     }
 
@@ -27,6 +73,16 @@ impl Engine {
         self.propellant_mass_per_second() * self.burn_time
     }
 
+    /// Chance of lighting once and burning for `burn_time` without failing.
+    /// Failures are spread evenly over the rated burn time, and come
+    /// `OVERBURN_PENALTY` times as often after it.
+    pub fn reliability(&self) -> f64 {
+        let failure_rate = -self.cycle_reliability.ln() / self.rated_burn_time;
+        let overburn = (self.burn_time - self.rated_burn_time).max(0.0);
+        let exposure = self.burn_time.min(self.rated_burn_time) + overburn * OVERBURN_PENALTY;
+        self.ignition_reliability * (-failure_rate * exposure).exp()
+    }
+
     pub fn with_burn_time(&self, burn_time: f64) -> Self {
         let mut result = self.clone();
         result.burn_time = burn_time;
@@ -34,300 +90,668 @@ impl Engine {
     }
 }
 
+// Reliability figures are rough estimates based on each engine's flight
+// record, in the spirit of TestFlight.
+
 pub const BELL_8048: Engine = Engine {
-    name: "Bell 8048 (XLR81-BA-5, Agena A)",
-    fuel_consumption: &[(UDMH, 8.8115), (IRFNA_III, 10.7262)],
+    name: Cow::Borrowed("Bell 8048 (XLR81-BA-5, Agena A)"),
+    fuel_consumption: Cow::Borrowed(&[(UDMH, 8.8115), (IRFNA_III, 10.7262)]),
     isp: 276.0,
+    sea_level_isp: 120.0,
     thrust: 67.0,
     mass: 132.0,
     burn_time: 120.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 120.0,
+    ignition_reliability: 0.95,
+    cycle_reliability: 0.96,
+    vernier: false,
 };
 
 pub const BELL_8081: Engine = Engine {
-    name: "Bell 8081 (XLR81-BA-7, Agena B)",
-    fuel_consumption: &[(UDMH, 8.9903), (IRFNA_III, 11.0327)],
+    name: Cow::Borrowed("Bell 8081 (XLR81-BA-7, Agena B)"),
+    fuel_consumption: Cow::Borrowed(&[(UDMH, 8.9903), (IRFNA_III, 11.0327)]),
     isp: 285.0,
+    sea_level_isp: 100.0,
     thrust: 71.0,
     mass: 132.0,
     burn_time: 240.0,
+    cost: 0.0,
+    ignitions: Some(2),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 240.0,
+    ignition_reliability: 0.96,
+    cycle_reliability: 0.97,
+    vernier: false,
 };
 
 pub const BELL_8096: Engine = Engine {
-    name: "Bell 8096 (XLR81-BA-13, Gemini ATV)",
-    fuel_consumption: &[(UDMH, 8.8049), (IRFNA_III, 10.8052)],
+    name: Cow::Borrowed("Bell 8096 (XLR81-BA-13, Gemini ATV)"),
+    fuel_consumption: Cow::Borrowed(&[(UDMH, 8.8049), (IRFNA_III, 10.8052)]),
     isp: 291.0,
+    sea_level_isp: 100.0,
     thrust: 71.0,
     mass: 132.0,
     burn_time: 240.0,
+    cost: 0.0,
+    ignitions: Some(15),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 240.0,
+    ignition_reliability: 0.98,
+    cycle_reliability: 0.98,
+    vernier: false,
 };
 
 pub const LR43_NA_5: Engine = Engine {
-    name: "LR43-NA-5",
-    fuel_consumption: &[(LIQUID_OXYGEN, 49.3816), (KEROSENE, 30.5239)],
+    name: Cow::Borrowed("LR43-NA-5"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 49.3816), (KEROSENE, 30.5239)]),
     isp: 301.0,
+    sea_level_isp: 215.0,
     thrust: 240.2,
     mass: 844.0,
     burn_time: 330.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 330.0,
+    ignition_reliability: 0.92,
+    cycle_reliability: 0.94,
+    vernier: false,
 };
 
 pub const LR105_NA_3: Engine = Engine {
-    name: "LR105-NA-3",
-    fuel_consumption: &[(LIQUID_OXYGEN, 70.5326), (KEROSENE, 43.5978)],
+    name: Cow::Borrowed("LR105-NA-3"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 70.5326), (KEROSENE, 43.5978)]),
     isp: 309.0,
+    sea_level_isp: 215.0,
     thrust: 352.2,
     mass: 844.0,
     burn_time: 330.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 330.0,
+    ignition_reliability: 0.93,
+    cycle_reliability: 0.95,
+    vernier: false,
 };
 
 pub const LR105_NA_5: Engine = Engine {
-    name: "LR105-NA-5",
-    fuel_consumption: &[(LIQUID_OXYGEN, 72.3793), (KEROSENE, 44.7393)],
+    name: Cow::Borrowed("LR105-NA-5"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 72.3793), (KEROSENE, 44.7393)]),
     isp: 313.0,
+    sea_level_isp: 220.0,
     thrust: 366.1,
     mass: 758.0,
     burn_time: 350.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 350.0,
+    ignition_reliability: 0.95,
+    cycle_reliability: 0.96,
+    vernier: false,
 };
 
 pub const LR105_NA_6: Engine = Engine {
-    name: "LR105-NA-6",
-    fuel_consumption: &[(LIQUID_OXYGEN, 73.7830), (KEROSENE, 45.6070)],
+    name: Cow::Borrowed("LR105-NA-6"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 73.7830), (KEROSENE, 45.6070)]),
     isp: 313.0,
+    sea_level_isp: 220.0,
     thrust: 373.2,
     mass: 758.0,
     burn_time: 350.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 350.0,
+    ignition_reliability: 0.96,
+    cycle_reliability: 0.97,
+    vernier: false,
 };
 
 pub const LR105_NA_7_1: Engine = Engine {
-    name: "LR105-NA-7.1",
-    fuel_consumption: &[(LIQUID_OXYGEN, 75.4324), (KEROSENE, 46.6265)],
+    name: Cow::Borrowed("LR105-NA-7.1"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 75.4324), (KEROSENE, 46.6265)]),
     isp: 316.0,
+    sea_level_isp: 220.0,
     thrust: 385.2,
     mass: 862.0,
     burn_time: 350.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 350.0,
+    ignition_reliability: 0.98,
+    cycle_reliability: 0.98,
+    vernier: false,
 };
 
 pub const LR101_NA_3: Engine = Engine {
-    name: "LR101-NA-3 Vernier",
-    fuel_consumption: &[(LIQUID_OXYGEN, 1.3296), (KEROSENE, 0.8222)],
+    name: Cow::Borrowed("LR101-NA-3 Vernier"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 1.3296), (KEROSENE, 0.8222)]),
     isp: 238.0,
+    sea_level_isp: 196.0,
     thrust: 4.448,
     mass: 24.0,
     burn_time: 360.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 360.0,
+    ignition_reliability: 0.97,
+    cycle_reliability: 0.98,
+    vernier: true,
 };
 
 pub const LR101_NA_11: Engine = Engine {
-    name: "LR101-NA-11 Vernier",
-    fuel_consumption: &[(LIQUID_OXYGEN, 1.3153), (KEROSENE, 0.8512)],
+    name: Cow::Borrowed("LR101-NA-11 Vernier"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 1.3153), (KEROSENE, 0.8512)]),
     isp: 249.0,
+    sea_level_isp: 206.0,
     thrust: 5.369,
     mass: 24.0,
     burn_time: 360.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 360.0,
+    ignition_reliability: 0.98,
+    cycle_reliability: 0.985,
+    vernier: true,
 };
 
 pub const LR43_NA_3: Engine = Engine {
-    name: "LR43-NA-3",
-    fuel_consumption: &[(LIQUID_OXYGEN, 148.5149), (KEROSENE, 91.8005)],
+    name: Cow::Borrowed("LR43-NA-3"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 148.5149), (KEROSENE, 91.8005)]),
     isp: 278.0,
+    sea_level_isp: 248.0,
     thrust: 667.2,
     mass: 720.0,
     burn_time: 135.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 135.0,
+    ignition_reliability: 0.9,
+    cycle_reliability: 0.92,
+    vernier: false,
 };
 
 pub const LR89_NA_3: Engine = Engine {
-    name: "LR89-NA-3",
-    fuel_consumption: &[(LIQUID_OXYGEN, 166.4868), (KEROSENE, 102.9093)],
+    name: Cow::Borrowed("LR89-NA-3"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 166.4868), (KEROSENE, 102.9093)]),
     isp: 282.0,
+    sea_level_isp: 248.0,
     thrust: 758.7,
     mass: 641.0,
     burn_time: 135.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 135.0,
+    ignition_reliability: 0.93,
+    cycle_reliability: 0.95,
+    vernier: false,
 };
 
 pub const LR89_NA_5: Engine = Engine {
-    name: "LR89-NA-5",
-    fuel_consumption: &[(LIQUID_OXYGEN, 177.4070), (KEROSENE, 109.6594)],
+    name: Cow::Borrowed("LR89-NA-5"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 177.4070), (KEROSENE, 109.6594)]),
     isp: 290.0,
+    sea_level_isp: 259.0,
     thrust: 831.4,
     mass: 828.0,
     burn_time: 150.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 150.0,
+    ignition_reliability: 0.95,
+    cycle_reliability: 0.96,
+    vernier: false,
 };
 
 pub const LR89_NA_6: Engine = Engine {
-    name: "LR89-NA-6",
-    fuel_consumption: &[(LIQUID_OXYGEN, 180.6504), (KEROSENE, 111.6642)],
+    name: Cow::Borrowed("LR89-NA-6"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 180.6504), (KEROSENE, 111.6642)]),
     isp: 290.0,
+    sea_level_isp: 259.0,
     thrust: 846.6,
     mass: 883.0,
     burn_time: 160.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 160.0,
+    ignition_reliability: 0.96,
+    cycle_reliability: 0.97,
+    vernier: false,
 };
 
 pub const LR89_NA_7_1: Engine = Engine {
-    name: "LR89-NA-7.1",
-    fuel_consumption: &[(LIQUID_OXYGEN, 197.3125), (KEROSENE, 121.9634)],
+    name: Cow::Borrowed("LR89-NA-7.1"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 197.3125), (KEROSENE, 121.9634)]),
     isp: 292.2,
+    sea_level_isp: 263.0,
     thrust: 931.7,
     mass: 1018.0,
     burn_time: 165.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 165.0,
+    ignition_reliability: 0.98,
+    cycle_reliability: 0.98,
+    vernier: false,
 };
 
 pub const LR79_NA_9: Engine = Engine {
-    name: "LR79-NA-9",
-    fuel_consumption: &[(LIQUID_OXYGEN, 166.2447), (KEROSENE, 107.5894)],
+    name: Cow::Borrowed("LR79-NA-9"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 166.2447), (KEROSENE, 107.5894)]),
     isp: 284.0,
+    sea_level_isp: 248.0,
     thrust: 774.0,
     mass: 934.0,
     burn_time: 165.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 165.0,
+    ignition_reliability: 0.94,
+    cycle_reliability: 0.95,
+    vernier: false,
 };
 
 pub const LR79_NA_11: Engine = Engine {
-    name: "LR79-NA-11",
-    fuel_consumption: &[(LIQUID_OXYGEN, 181.1651), (KEROSENE, 117.2455)],
+    name: Cow::Borrowed("LR79-NA-11"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_OXYGEN, 181.1651), (KEROSENE, 117.2455)]),
     isp: 286.2,
+    sea_level_isp: 250.0,
     thrust: 850.0,
     mass: 980.0,
     burn_time: 165.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 165.0,
+    ignition_reliability: 0.96,
+    cycle_reliability: 0.97,
+    vernier: false,
 };
 
 pub const AJ10_42: Engine = Engine {
-    name: "AJ10-42",
-    fuel_consumption: &[(UDMH, 4.1946), (IRFNA_III, 6.1370)],
+    name: Cow::Borrowed("AJ10-42"),
+    fuel_consumption: Cow::Borrowed(&[(UDMH, 4.1946), (IRFNA_III, 6.1370)]),
     isp: 267.0,
+    sea_level_isp: 130.0,
     thrust: 33.0,
     mass: 80.0,
     burn_time: 150.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 150.0,
+    ignition_reliability: 0.92,
+    cycle_reliability: 0.94,
+    vernier: false,
 };
 
 pub const AJ10_142: Engine = Engine {
-    name: "AJ10-142",
-    fuel_consumption: &[(UDMH, 4.3052), (IWFNA, 6.2987)],
+    name: Cow::Borrowed("AJ10-142"),
+    fuel_consumption: Cow::Borrowed(&[(UDMH, 4.3052), (IWFNA, 6.2987)]),
     isp: 270.0,
+    sea_level_isp: 130.0,
     thrust: 30.444,
     mass: 80.0,
     burn_time: 150.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 150.0,
+    ignition_reliability: 0.95,
+    cycle_reliability: 0.96,
+    vernier: false,
 };
 
 pub const AJ10_104: Engine = Engine {
-    name: "AJ10-104",
-    fuel_consumption: &[(UDMH, 4.2831), (IRFNA_III, 5.7219)],
+    name: Cow::Borrowed("AJ10-104"),
+    fuel_consumption: Cow::Borrowed(&[(UDMH, 4.2831), (IRFNA_III, 5.7219)]),
     isp: 278.0,
+    sea_level_isp: 130.0,
     thrust: 35.1,
     mass: 90.0,
     burn_time: 300.0,
+    cost: 0.0,
+    ignitions: Some(2),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 300.0,
+    ignition_reliability: 0.96,
+    cycle_reliability: 0.97,
+    vernier: false,
 };
 
-
 pub const BABY_SERGEANT: Engine = Engine {
-    name: "Baby Sergeant",
-    fuel_consumption: &[(PSPC, 1.9950)],
+    name: Cow::Borrowed("Baby Sergeant"),
+    fuel_consumption: Cow::Borrowed(&[(PSPC, 1.9950)]),
     isp: 235.0,
+    sea_level_isp: 200.0,
     thrust: 8.0,
     mass: 5.670,
     burn_time: 6.345,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: false,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 6.345,
+    ignition_reliability: 0.97,
+    cycle_reliability: 0.99,
+    vernier: false,
 };
 
 pub const HYDRAZINE_THRUSTER: Engine = Engine {
-    name: "1kN Thruster",
-    fuel_consumption:  &[(HYDRAZINE, 0.4911)],
+    name: Cow::Borrowed("1kN Thruster (Hydrazine)"),
+    fuel_consumption: Cow::Borrowed(&[(HYDRAZINE, 0.4911)]),
     isp: 198.0,
+    sea_level_isp: 100.0,
     thrust: 0.957,
     mass: 16.0,
     burn_time: 20.0 * 60.0,
+    cost: 0.0,
+    ignitions: None,
+    ullage: false,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 20.0 * 60.0,
+    ignition_reliability: 0.999,
+    cycle_reliability: 0.999,
+    vernier: false,
 };
 
 pub const CAVEA_THRUSTER: Engine = Engine {
-    name: "2.2/3.6kN Thruster",
-    fuel_consumption: &[(CAVEA_B, 0.7786)],
+    name: Cow::Borrowed("2.2/3.6kN Thruster (Cavea-B)"),
+    fuel_consumption: Cow::Borrowed(&[(CAVEA_B, 0.7786)]),
     isp: 258.225,
+    sea_level_isp: 130.0,
     thrust: 2.959,
     mass: 34.0,
     burn_time: 20.0 * 60.0,
+    cost: 0.0,
+    ignitions: None,
+    ullage: false,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 20.0 * 60.0,
+    ignition_reliability: 0.999,
+    cycle_reliability: 0.999,
+    vernier: false,
 };
 
 pub const THRUSTER_1: Engine = Engine {
-    name: "1kN Thruster",
-    fuel_consumption: &[(AEROZINE50, 0.3022), (NTO, 0.2998)],
+    name: Cow::Borrowed("1kN Thruster"),
+    fuel_consumption: Cow::Borrowed(&[(AEROZINE50, 0.3022), (NTO, 0.2998)]),
     isp: 262.625,
+    sea_level_isp: 130.0,
     thrust: 1.82,
     mass: 15.0,
     burn_time: 20.0 * 60.0,
+    cost: 0.0,
+    ignitions: None,
+    ullage: false,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 20.0 * 60.0,
+    ignition_reliability: 0.999,
+    cycle_reliability: 0.999,
+    vernier: false,
 };
 
 pub const THRUSTER_2: Engine = Engine {
-    name: "2.2/3.6kN Thruster",
-    fuel_consumption: &[(AEROZINE50, 0.5634), (NTO, 0.5589)],
+    name: Cow::Borrowed("2.2/3.6kN Thruster"),
+    fuel_consumption: Cow::Borrowed(&[(AEROZINE50, 0.5634), (NTO, 0.5589)]),
     isp: 281.725,
+    sea_level_isp: 140.0,
     thrust: 3.64,
     mass: 32.0,
     burn_time: 20.0 * 60.0,
+    cost: 0.0,
+    ignitions: None,
+    ullage: false,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 20.0 * 60.0,
+    ignition_reliability: 0.999,
+    cycle_reliability: 0.999,
+    vernier: false,
 };
 
 pub const ALTAIR: Engine = Engine {
-    name: "Altair",
-    fuel_consumption: &[(PSPC, 3.4339)],
+    name: Cow::Borrowed("Altair"),
+    fuel_consumption: Cow::Borrowed(&[(PSPC, 3.4339)]),
     isp: 256.0,
+    sea_level_isp: 210.0,
     thrust: 15.0,
     mass: 30.0,
     burn_time: 34.8,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: false,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 34.8,
+    ignition_reliability: 0.96,
+    cycle_reliability: 0.98,
+    vernier: false,
 };
 
 pub const CASTOR_1: Engine = Engine {
-    name: "Castor 1",
-    fuel_consumption: &[(HTPB, 66.7076)],
+    name: Cow::Borrowed("Castor 1"),
+    fuel_consumption: Cow::Borrowed(&[(HTPB, 66.7076)]),
     isp: 247.0,
+    sea_level_isp: 215.0,
     thrust: 268.632,
     mass: 535.0,
     burn_time: 28.1,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: false,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 28.1,
+    ignition_reliability: 0.97,
+    cycle_reliability: 0.98,
+    vernier: false,
 };
 
 pub const H1: Engine = Engine {
-    name: "H1 Saturn I",
-    fuel_consumption: &[(KEROSENE, 126.1482), (LIQUID_OXYGEN, 202.1917)],
+    name: Cow::Borrowed("H1 Saturn I"),
+    fuel_consumption: Cow::Borrowed(&[(KEROSENE, 126.1482), (LIQUID_OXYGEN, 202.1917)]),
     isp: 289.0,
+    sea_level_isp: 255.0,
     thrust: 947.0,
     mass: 635.0,
     burn_time: 150.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 150.0,
+    ignition_reliability: 0.98,
+    cycle_reliability: 0.985,
+    vernier: false,
 };
 
 pub const H1B: Engine = Engine {
-    name: "H1 Saturn IB",
-    fuel_consumption: &[(KEROSENE, 133.9858), (LIQUID_OXYGEN, 214.7539)],
+    name: Cow::Borrowed("H1 Saturn IB"),
+    fuel_consumption: Cow::Borrowed(&[(KEROSENE, 133.9858), (LIQUID_OXYGEN, 214.7539)]),
     isp: 296.0,
+    sea_level_isp: 263.0,
     thrust: 1030.2,
     mass: 988.0,
     burn_time: 180.0,
+    cost: 0.0,
+    ignitions: Some(1),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 180.0,
+    ignition_reliability: 0.99,
+    cycle_reliability: 0.99,
+    vernier: false,
 };
 
 pub const RL10A_1: Engine = Engine {
-    name: "RL10A-1",
-    fuel_consumption: &[(LIQUID_HYDROGEN, 38.0877), (LIQUID_OXYGEN, 11.8241)],
+    name: Cow::Borrowed("RL10A-1"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_HYDROGEN, 38.0877), (LIQUID_OXYGEN, 11.8241)]),
     isp: 422.0,
+    sea_level_isp: 200.0,
     thrust: 67.0,
     mass: 145.0,
     burn_time: 430.0,
+    cost: 0.0,
+    ignitions: Some(2),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 430.0,
+    ignition_reliability: 0.95,
+    cycle_reliability: 0.96,
+    vernier: false,
 };
 
 pub const RL10A_3_1: Engine = Engine {
-    name: "RL10A-3-1",
-    fuel_consumption: &[(LIQUID_HYDROGEN, 37.1201), (LIQUID_OXYGEN, 11.5237)],
+    name: Cow::Borrowed("RL10A-3-1"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_HYDROGEN, 37.1201), (LIQUID_OXYGEN, 11.5237)]),
     isp: 433.0,
+    sea_level_isp: 200.0,
     thrust: 67.0,
     mass: 139.0,
     burn_time: 470.0,
+    cost: 0.0,
+    ignitions: Some(3),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 470.0,
+    ignition_reliability: 0.97,
+    cycle_reliability: 0.98,
+    vernier: false,
 };
 
 pub const RL10A_3_3: Engine = Engine {
-    name: "RL10A-3-3",
-    fuel_consumption: &[(LIQUID_HYDROGEN, 36.2004), (LIQUID_OXYGEN, 11.2382)],
+    name: Cow::Borrowed("RL10A-3-3"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_HYDROGEN, 36.2004), (LIQUID_OXYGEN, 11.2382)]),
     isp: 444.0,
+    sea_level_isp: 200.0,
     thrust: 67.0,
     mass: 137.0,
     burn_time: 470.0,
+    cost: 0.0,
+    ignitions: Some(3),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 470.0,
+    ignition_reliability: 0.98,
+    cycle_reliability: 0.985,
+    vernier: false,
 };
 
 pub const J2_200KLBF: Engine = Engine {
-    name: "J-2-200klbf",
-    fuel_consumption: &[(LIQUID_HYDROGEN, 464.3834), (LIQUID_OXYGEN, 158.6155)],
+    name: Cow::Borrowed("J-2-200klbf"),
+    fuel_consumption: Cow::Borrowed(&[(LIQUID_HYDROGEN, 464.3834), (LIQUID_OXYGEN, 158.6155)]),
     isp: 424.0,
+    sea_level_isp: 200.0,
     thrust: 889.325,
     mass: 1610.0,
     burn_time: 350.0,
-};
+    cost: 0.0,
+    ignitions: Some(2),
+    ullage: true,
+    min_throttle: 1.0,
+    max_throttle: 1.0,
+    rated_burn_time: 350.0,
+    ignition_reliability: 0.97,
+    cycle_reliability: 0.98,
+    vernier: false,
+};
+
+pub const ALL: &[Engine] = &[
+    BELL_8048,
+    BELL_8081,
+    BELL_8096,
+    LR43_NA_5,
+    LR105_NA_3,
+    LR105_NA_5,
+    LR105_NA_6,
+    LR105_NA_7_1,
+    LR101_NA_3,
+    LR101_NA_11,
+    LR43_NA_3,
+    LR89_NA_3,
+    LR89_NA_5,
+    LR89_NA_6,
+    LR89_NA_7_1,
+    LR79_NA_9,
+    LR79_NA_11,
+    AJ10_42,
+    AJ10_142,
+    AJ10_104,
+    BABY_SERGEANT,
+    HYDRAZINE_THRUSTER,
+    CAVEA_THRUSTER,
+    THRUSTER_1,
+    THRUSTER_2,
+    ALTAIR,
+    CASTOR_1,
+    H1,
+    H1B,
+    RL10A_1,
+    RL10A_3_1,
+    RL10A_3_3,
+    J2_200KLBF,
+];
@@ -0,0 +1,128 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use serde_json;
+use toml;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    UnsupportedFormat(PathBuf),
+    UnknownFuel { engine: String, fuel: String },
+    InvalidFuel { fuel: String, field: &'static str, requirement: &'static str },
+    InvalidEngine { engine: String, field: &'static str, requirement: &'static str },
+    UnknownEngine(String),
+    UnknownDestination(String),
+    UnknownTankType(String),
+    UnknownInsulation(String),
+    UnknownBody(String),
+    NoRoute { from: String, to: String },
+    NoEngines,
+    BoostersWithBoosterSection,
+    AirLitCrossfeed,
+    JettisonTrigger,
+    JettisonTime,
+    JettisonFraction,
+    JettisonMass,
+    ComponentJettison,
+    JettisonAltitude,
+    Stage(usize, Box<Error>),
+    File(PathBuf, Box<Error>),
+}
+
+impl Error {
+    pub fn in_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        Error::File(path.into(), Box::new(self))
+    }
+
+    pub fn in_stage(self, index: usize) -> Self {
+        Error::Stage(index, Box::new(self))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => e.fmt(f),
+            Error::Toml(ref e) => e.fmt(f),
+            Error::Json(ref e) => e.fmt(f),
+            Error::UnsupportedFormat(ref path) => write!(
+                f,
+                "Don't know how to read {}, expected a .toml or .json file",
+                path.display(),
+            ),
+            Error::UnknownFuel { ref engine, ref fuel } => write!(
+                f,
+                "Engine `{}` uses unknown fuel `{}`",
+                engine,
+                fuel,
+            ),
+            Error::InvalidFuel { ref fuel, field, requirement } => write!(
+                f,
+                "Fuel `{}`: `{}` {}",
+                fuel,
+                field,
+                requirement,
+            ),
+            Error::InvalidEngine { ref engine, field, requirement } => write!(
+                f,
+                "Engine `{}`: `{}` {}",
+                engine,
+                field,
+                requirement,
+            ),
+            Error::UnknownEngine(ref name) => write!(f, "Unknown engine `{}`", name),
+            Error::UnknownDestination(ref name) => write!(f, "Unknown destination `{}`", name),
+            Error::UnknownTankType(ref name) => write!(f, "Unknown tank type `{}`", name),
+            Error::UnknownInsulation(ref name) => write!(f, "Unknown insulation `{}`", name),
+            Error::UnknownBody(ref name) => write!(f, "Unknown body `{}`", name),
+            Error::NoRoute { ref from, ref to } => write!(f, "No route from `{}` to `{}`", from, to),
+            Error::NoEngines => write!(f, "Stages must have at least one engine"),
+            Error::BoostersWithBoosterSection => write!(f, "Stages can't have both boosters and a booster section"),
+            Error::AirLitCrossfeed => write!(f, "Crossfeeding boosters must light with the core"),
+            Error::JettisonTrigger => write!(f, "Jettisons need either a `time` or a `propellant_fraction`"),
+            Error::JettisonTime => write!(f, "Jettison times must be after ignition"),
+            Error::JettisonFraction => write!(f, "Jettison propellant fractions must be above 0 and at most 1"),
+            Error::JettisonMass => write!(f, "Jettisons can't drop more than the stage's dry mass"),
+            Error::ComponentJettison => write!(f, "Fairings and escape systems need either a `jettison_time` or a `jettison_altitude`"),
+            Error::JettisonAltitude => write!(f, "Jettisoning at an altitude needs a body to fly the ascent over"),
+            Error::Stage(index, ref e) => write!(f, "stage {}: {}", index + 1, e),
+            Error::File(ref path, ref e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::Io(ref e) => Some(e),
+            Error::Toml(ref e) => Some(e),
+            Error::Json(ref e) => Some(e),
+            Error::Stage(_, ref e) => Some(&**e),
+            Error::File(_, ref e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Toml(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
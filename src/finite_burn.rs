@@ -0,0 +1,257 @@
+//! Finite-burn losses for long burns from orbit.
+//!
+//! The delta-v map treats every burn as impulsive, spending all of its
+//! delta-v at periapsis where the Oberth effect is strongest. A low thrust
+//! stage spreads the burn either side of periapsis, higher up and slower, and
+//! needs more delta-v to leave with the same orbital energy. That extra
+//! delta-v is the loss.
+//!
+//! Burns are simulated as a 2D point mass thrusting prograde, centred on
+//! periapsis. Perigee kicks split the burn into several shorter ones, each
+//! raising the apoapsis and waiting for the next pass through periapsis. The
+//! coasts between kicks take no propellant and are ignored.
+
+use bodies::Body;
+use engines::{Engine, VACUUM};
+use stage::Stage;
+
+/// Largest number of perigee kicks `perigee_kicks` will suggest
+pub const MAX_KICKS: usize = 10;
+/// Integration steps per burn, and per coast back from periapsis
+const STEPS: usize = 2000;
+
+/// The orbit a maneuver starts from, as radii in m
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartOrbit {
+    pub periapsis: f64,
+    pub apoapsis: f64,
+}
+
+impl StartOrbit {
+    pub fn circular(radius: f64) -> Self {
+        StartOrbit { periapsis: radius, apoapsis: radius }
+    }
+
+    fn semi_major_axis(&self) -> f64 {
+        (self.periapsis + self.apoapsis) / 2.0
+    }
+
+    fn periapsis_speed(&self, gravitational_parameter: f64) -> f64 {
+        (gravitational_parameter * (2.0 / self.periapsis - 1.0 / self.semi_major_axis())).sqrt()
+    }
+
+    fn energy(&self, gravitational_parameter: f64) -> f64 {
+        -gravitational_parameter / (2.0 * self.semi_major_axis())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FiniteBurn {
+    /// Delta-v the stage actually spends, in m/s
+    pub delta_v: f64,
+    pub burn_time: f64,
+    /// Delta-v spent beyond this burn's share of the impulsive maneuver
+    pub loss: f64,
+}
+
+/// Flies an impulsive `delta_v` from periapsis as a single burn of `stage`.
+/// `None` if the stage runs dry first.
+pub fn finite_burn(stage: &dyn Stage, body: &Body, orbit: StartOrbit, delta_v: f64) -> Option<FiniteBurn> {
+    kicks(stage, body, orbit, delta_v, 1).map(|burns| burns[0])
+}
+
+/// The fewest perigee kicks, up to `MAX_KICKS`, which keep the total loss
+/// under `max_loss` as a fraction of `delta_v`. Only worth asking for when a
+/// single burn loses more than that. `None` if no split does it.
+pub fn perigee_kicks(stage: &dyn Stage, body: &Body, orbit: StartOrbit, delta_v: f64, max_loss: f64) -> Option<Vec<FiniteBurn>> {
+    (2..MAX_KICKS + 1)
+        .filter_map(|count| kicks(stage, body, orbit, delta_v, count))
+        .find(|burns| burns.iter().map(|b| b.loss).sum::<f64>() <= max_loss * delta_v)
+}
+
+/// Splits `delta_v` into `count` burns, each adding an equal share of the
+/// impulsive speed at the original periapsis
+fn kicks(stage: &dyn Stage, body: &Body, orbit: StartOrbit, delta_v: f64, count: usize) -> Option<Vec<FiniteBurn>> {
+    let mu = body.gravitational_parameter;
+    let engines = stage.engines();
+    let thrust = engines.iter().map(|e| e.thrust_at(VACUUM) * 1000.0).sum::<f64>();
+    let mass_flow = engines.iter().map(Engine::propellant_mass_per_second).sum::<f64>();
+    if thrust <= 0.0 || mass_flow <= 0.0 {
+        return None;
+    }
+    let exhaust_velocity = thrust / mass_flow;
+
+    let start_speed = orbit.periapsis_speed(mu);
+    let start_energy = orbit.energy(mu);
+    let mut burn_time_left = stage.burn_time();
+    let mut mass = stage.wet_mass();
+    let mut current = orbit;
+    let mut burns = Vec::with_capacity(count);
+
+    for i in 1..count + 1 {
+        if current.apoapsis.is_infinite() {
+            return None;
+        }
+        let speed = start_speed + delta_v * i as f64 / count as f64;
+        let target_energy = start_energy + (speed * speed - start_speed * start_speed) / 2.0;
+
+        // The loss barely changes with the length of the burn, so correcting
+        // by the energy still missing converges in a few passes
+        let needed = |orbit: &StartOrbit, energy: f64| {
+            let speed = orbit.periapsis_speed(mu);
+            (speed * speed + 2.0 * (energy - orbit.energy(mu))).max(0.0).sqrt() - speed
+        };
+        let mut spent = needed(&current, target_energy);
+        let mut result = None;
+        for _ in 0..6 {
+            let burn_time = mass * (1.0 - (-spent / exhaust_velocity).exp()) / mass_flow;
+            if burn_time > burn_time_left {
+                return None;
+            }
+            let end = fly(mu, current, mass, thrust, mass_flow, burn_time);
+            let energy = end.energy(mu);
+            spent += needed(&current, target_energy) - needed(&current, energy);
+            result = Some((burn_time, end));
+        }
+
+        let (burn_time, end) = result.expect("the loop runs at least once");
+        burns.push(FiniteBurn {
+            delta_v: spent,
+            burn_time,
+            loss: spent - delta_v / count as f64,
+        });
+        burn_time_left -= burn_time;
+        mass -= mass_flow * burn_time;
+        current = end.orbit(mu);
+    }
+
+    Some(burns)
+}
+
+/// Position, velocity and mass
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct State([f64; 5]);
+
+impl State {
+    fn radius(&self) -> f64 {
+        self.0[0].hypot(self.0[1])
+    }
+
+    fn speed(&self) -> f64 {
+        self.0[2].hypot(self.0[3])
+    }
+
+    fn energy(&self, mu: f64) -> f64 {
+        self.speed().powi(2) / 2.0 - mu / self.radius()
+    }
+
+    /// Periapsis and apoapsis radii of the orbit passing through this state.
+    /// The apoapsis is infinite when it escapes.
+    fn orbit(&self, mu: f64) -> StartOrbit {
+        let energy = self.energy(mu);
+        let angular_momentum = self.0[0] * self.0[3] - self.0[1] * self.0[2];
+        let eccentricity = (1.0 + 2.0 * energy * angular_momentum * angular_momentum / (mu * mu))
+            .max(0.0).sqrt();
+        let periapsis = angular_momentum * angular_momentum / mu / (1.0 + eccentricity);
+        if energy >= 0.0 {
+            StartOrbit { periapsis, apoapsis: f64::INFINITY }
+        } else {
+            StartOrbit { periapsis, apoapsis: -mu / (2.0 * energy) * (1.0 + eccentricity) }
+        }
+    }
+
+    fn derivative(&self, mu: f64, thrust: f64, mass_flow: f64) -> State {
+        let [x, y, vx, vy, mass] = self.0;
+        let r = self.radius();
+        let gravity = -mu / (r * r * r);
+        let speed = self.speed();
+        let acceleration = if speed > 0.0 { thrust / mass / speed } else { 0.0 };
+        State([
+            vx,
+            vy,
+            gravity * x + acceleration * vx,
+            gravity * y + acceleration * vy,
+            -mass_flow,
+        ])
+    }
+
+    fn add(&self, other: &State, factor: f64) -> State {
+        let mut sum = self.0;
+        for (a, b) in sum.iter_mut().zip(&other.0) {
+            *a += b * factor;
+        }
+        State(sum)
+    }
+
+    /// Fourth order Runge-Kutta, prograde thrust the whole step
+    fn step(&self, mu: f64, thrust: f64, mass_flow: f64, dt: f64) -> State {
+        let k1 = self.derivative(mu, thrust, mass_flow);
+        let k2 = self.add(&k1, dt / 2.0).derivative(mu, thrust, mass_flow);
+        let k3 = self.add(&k2, dt / 2.0).derivative(mu, thrust, mass_flow);
+        let k4 = self.add(&k3, dt).derivative(mu, thrust, mass_flow);
+        self.add(&k1, dt / 6.0)
+            .add(&k2, dt / 3.0)
+            .add(&k3, dt / 3.0)
+            .add(&k4, dt / 6.0)
+    }
+}
+
+/// Coasts back half the burn from periapsis, then burns prograde through it
+fn fly(mu: f64, orbit: StartOrbit, mass: f64, thrust: f64, mass_flow: f64, burn_time: f64) -> State {
+    let mut state = State([orbit.periapsis, 0.0, 0.0, orbit.periapsis_speed(mu), mass]);
+    let dt = burn_time / STEPS as f64;
+    for _ in 0..STEPS {
+        state = state.step(mu, 0.0, 0.0, -dt / 2.0);
+    }
+    for _ in 0..STEPS {
+        state = state.step(mu, thrust, mass_flow, dt);
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bodies::rss::EARTH;
+    use engines::BELL_8081;
+    use stage::SimpleStage;
+
+    fn low_orbit() -> StartOrbit {
+        StartOrbit::circular(EARTH.low_orbit_radius())
+    }
+
+    #[test]
+    fn short_burns_are_impulsive() {
+        let stage = SimpleStage { dry_mass: 700.0, engines: vec![BELL_8081] };
+        let burn = finite_burn(&stage, &EARTH, low_orbit(), 10.0).unwrap();
+
+        // m (1 - e^(-dv / ve)) / mdot, with ve = thrust / mdot
+        let mass_flow = BELL_8081.propellant_mass_per_second();
+        let exhaust_velocity = BELL_8081.thrust_at(VACUUM) * 1000.0 / mass_flow;
+        let burn_time = stage.wet_mass() * (1.0 - (-10.0 / exhaust_velocity).exp()) / mass_flow;
+        assert!(burn.loss.abs() < 0.01, "{:?}", burn);
+        assert!((burn.burn_time - burn_time).abs() < 0.01, "{:?}", burn);
+    }
+
+    #[test]
+    fn kicks_cut_the_loss_of_long_burns() {
+        // Most of the way to escape, the burn stretches well away from
+        // periapsis
+        let stage = SimpleStage { dry_mass: 3000.0, engines: vec![BELL_8081] };
+        let single = finite_burn(&stage, &EARTH, low_orbit(), 3000.0).unwrap();
+        assert!(single.loss > 3.0, "{:?}", single);
+
+        let kicks = perigee_kicks(&stage, &EARTH, low_orbit(), 3000.0, 0.001).unwrap();
+        let loss = kicks.iter().map(|k| k.loss).sum::<f64>();
+        let spent = kicks.iter().map(|k| k.delta_v).sum::<f64>();
+        assert!(kicks.len() > 1 && kicks.len() <= MAX_KICKS);
+        assert!(loss <= 0.001 * 3000.0 && loss < single.loss);
+        assert!((spent - (3000.0 + loss)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn stages_can_run_dry() {
+        let stage = SimpleStage { dry_mass: 5000.0, engines: vec![BELL_8081] };
+        assert_eq!(None, finite_burn(&stage, &EARTH, low_orbit(), stage.delta_v() + 100.0));
+    }
+}
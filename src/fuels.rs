@@ -1,21 +1,40 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Fuel {
-    pub name: &'static str,
+    pub name: Cow<'static, str>,
     pub density: f64,
+    /// Fraction lost per day in an uninsulated tank
+    pub boiloff_rate: f64,
 }
 
-pub const KEROSENE: Fuel = Fuel { name: "Kerosene", density: 0.82 };
-pub const LIQUID_OXYGEN: Fuel = Fuel { name: "LqdOxygen", density: 1.141 };
+pub const KEROSENE: Fuel = Fuel { name: Cow::Borrowed("Kerosene"), density: 0.82, boiloff_rate: 0.0 };
+pub const LIQUID_OXYGEN: Fuel = Fuel { name: Cow::Borrowed("LqdOxygen"), density: 1.141, boiloff_rate: 0.01 };
+
+pub const UDMH: Fuel = Fuel { name: Cow::Borrowed("UDMH"), density: 0.791, boiloff_rate: 0.0 };
+pub const IRFNA_III: Fuel = Fuel { name: Cow::Borrowed("IRFNA-III"), density: 1.658, boiloff_rate: 0.0 };
+pub const IWFNA: Fuel = Fuel { name: Cow::Borrowed("IWFNA"), density: 1.513, boiloff_rate: 0.0 };
+pub const LIQUID_HYDROGEN: Fuel = Fuel { name: Cow::Borrowed("Liquid Hydrogen"), density: 0.07085, boiloff_rate: 0.05 };
 
-pub const UDMH: Fuel = Fuel { name: "UDMH", density: 0.791 };
-pub const IRFNA_III: Fuel = Fuel { name: "IRFNA-III", density: 1.658 };
-pub const IWFNA: Fuel = Fuel { name: "IWFNA", density: 1.513 };
-pub const LIQUID_HYDROGEN: Fuel = Fuel { name: "Liquid Hydrogen", density: 0.07085 };
+pub const PSPC: Fuel = Fuel { name: Cow::Borrowed("PSPC"), density: 1.74, boiloff_rate: 0.0 };
+pub const HTPB: Fuel = Fuel { name: Cow::Borrowed("HTPB"), density: 1.77, boiloff_rate: 0.0 };
 
-pub const PSPC: Fuel = Fuel { name: "PSPC", density: 1.74 };
-pub const HTPB: Fuel = Fuel { name: "HTPB", density: 1.77 };
+pub const HYDRAZINE: Fuel = Fuel { name: Cow::Borrowed("Hydrazine"), density: 1.004, boiloff_rate: 0.0 };
+pub const CAVEA_B: Fuel = Fuel { name: Cow::Borrowed("Cavea-B"), density: 1.501, boiloff_rate: 0.0 };
+pub const AEROZINE50: Fuel = Fuel { name: Cow::Borrowed("Aerozine50"), density: 0.9, boiloff_rate: 0.0 };
+pub const NTO: Fuel = Fuel { name: Cow::Borrowed("NTO"), density: 1.45, boiloff_rate: 0.0 };
 
-pub const HYDRAZINE: Fuel = Fuel { name: "Hydrazine", density: 1.004 };
-pub const CAVEA_B: Fuel = Fuel { name: "Cavea-B", density: 1.501 };
-pub const AEROZINE50: Fuel = Fuel { name: "Aerozine50", density: 0.9 };
-pub const NTO: Fuel = Fuel { name: "NTO", density: 1.45 };
+pub const ALL: &[Fuel] = &[
+    KEROSENE,
+    LIQUID_OXYGEN,
+    UDMH,
+    IRFNA_III,
+    IWFNA,
+    LIQUID_HYDROGEN,
+    PSPC,
+    HTPB,
+    HYDRAZINE,
+    CAVEA_B,
+    AEROZINE50,
+    NTO,
+];
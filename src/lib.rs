@@ -0,0 +1,47 @@
+//! Tools for designing rockets for Realism Overhaul.
+//!
+//! A `Rocket` is a list of stages, from the bottom up, plus a payload. Each
+//! stage implements `Stage`, which provides delta-v, TWR and burn time from
+//! the stage's engines and masses. Engines and fuels come either from the
+//! constants in `engines` and `fuels`, or from a `Catalog` loaded from data
+//! files.
+//!
+//! The command line tool's subcommands are in `commands`, which prints its
+//! results with `text`.
+
+extern crate ansi_term;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+
+pub mod ascent;
+pub mod bodies;
+pub mod catalog;
+pub mod commands;
+mod data_file;
+pub mod design;
+pub mod dispersion;
+pub mod destinations;
+pub mod engines;
+pub mod error;
+pub mod finite_burn;
+pub mod fuels;
+pub mod mission;
+pub mod report;
+pub mod rocket;
+pub mod search;
+pub mod sizing;
+pub mod stage;
+pub mod tanks;
+pub mod text;
+
+pub use catalog::Catalog;
+pub use design::RocketDesign;
+pub use engines::Engine;
+pub use error::Error;
+pub use fuels::Fuel;
+pub use report::Report;
+pub use rocket::{Limit, MaxPayload, PayloadTarget, Rocket, RocketStages, StageNumber};
+pub use stage::{BoostedStage, BoosterGroup, CrossfeedStage, Jettison, JettisonStage, JettisonTrigger, SimpleStage, Stage, StageWithPayload, SustainerStage};
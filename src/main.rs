@@ -1,383 +1,359 @@
-#![allow(dead_code)]
-extern crate ansi_term;
+extern crate clap;
+extern crate ksp_helper;
+
+use std::process;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use ksp_helper::*;
+use ksp_helper::ascent::AscentConfig;
+use ksp_helper::bodies::{System, SYSTEMS};
+use ksp_helper::commands::{self, BurnOptions, Format};
+use ksp_helper::destinations::DeltaVMap;
+use ksp_helper::dispersion::Dispersion;
+use ksp_helper::search::{Ranking, SearchConfig};
+use ksp_helper::tanks::{self, TankModel, TankType};
+use ksp_helper::text;
 
-mod engines;
-mod fuels;
-
-use std::fmt;
-use std::collections::HashMap;
-use std::marker::PhantomData;
-use ansi_term::Colour::{Red, Yellow, Blue};
-use self::engines::*;
-
-#[allow(unused_variables, unused_mut)]
 fn main() {
-    // Upper stages
-
-    // Lower stages
-
-    let mut rocket = Rocket {
-        stages: vec![],
-        payload_mass: 0.0,
-    };
-
-    // for (prop, amount) in rocket.stages().nth(0).unwrap().propellants_required() {
-    //     println!("{} {}", prop, amount);
-    // }
-    // println!("");
-
-    print_max_payloads(&mut rocket);
-    // rocket.set_payload_for_target_deltav(DV_TO_ORBIT);
-
-    println!("{:5}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}", "stage", "delta-v", "wet mass", "dry mass", "Start TWR", "End TWR", "burn time");
-    let reversed_stages = rocket.stages().enumerate().collect::<Vec<_>>().into_iter().rev();
-    for (i, stage) in reversed_stages {
-        println!("{:5}: {:6.0} m/s  {:10.0}  {:10.0}  {:>10.2}  {:>10.2}  {:>10}", i, stage.delta_v(), stage.wet_mass(), stage.dry_mass(), stage.twr(), stage.max_g_force(), BurnTime(stage.burn_time()));
+    let rocket_arg = Arg::with_name("rocket")
+        .help("TOML or JSON file describing the rocket")
+        .required(true);
+
+    let tank_types = tanks::ALL.iter().map(|t| t.name).collect::<Vec<_>>();
+    let tank_type_arg = Arg::with_name("tank-type")
+        .help("Size tanks by volume for this tank type instead of by mass fraction")
+        .long("tank-type")
+        .takes_value(true)
+        .possible_values(&tank_types)
+        .conflicts_with("tank-fraction");
+
+    let matches = App::new("ksp_helper")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(Arg::with_name("catalog")
+            .help("Load additional engines and fuels from a TOML or JSON file")
+            .long("catalog")
+            .short("c")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .global(true))
+        .arg(Arg::with_name("system")
+            .help("Planetary system to compute destinations for")
+            .long("system")
+            .short("s")
+            .possible_values(&["rss", "stock"])
+            .default_value("rss")
+            .global(true))
+        .subcommand(SubCommand::with_name("analyze")
+            .about("Shows the delta-v, mass and TWR of each stage")
+            .arg(rocket_arg.clone())
+            .arg(Arg::with_name("format")
+                .help("Output format")
+                .long("format")
+                .short("f")
+                .possible_values(&["text", "json", "csv"])
+                .default_value("text"))
+            .arg(Arg::with_name("table")
+                .help("Table to write as CSV")
+                .long("table")
+                .possible_values(&["stages", "destinations"])
+                .default_value("stages"))
+            .arg(number_arg("max-g", "Also show each stage's burn when throttled to stay under this many g")))
+        .subcommand(SubCommand::with_name("max-payload")
+            .about("Shows the heaviest payload the rocket can take to each destination")
+            .arg(rocket_arg.clone())
+            .arg(Arg::with_name("target")
+                .help("Only show the payload for this destination (e.g. GTO)")
+                .long("target")
+                .short("t")
+                .takes_value(true))
+            .arg(number_arg("min-twr", "Minimum lift-off TWR, 1 by default"))
+            .arg(number_arg("min-stage-twr", "Minimum vacuum TWR at ignition of the upper stages"))
+            .arg(number_arg("tolerance", "Precision of the result, 1 kg by default")))
+        .subcommand(SubCommand::with_name("size")
+            .about("Resizes the propellant load of each stage for the lightest rocket reaching a delta-v")
+            .arg(rocket_arg.clone())
+            .arg(number_arg("delta-v", "Delta-v to reach, in m/s").required(true))
+            .arg(number_arg("tank-fraction", "Tank dry mass per kg of propellant, 0.1 by default"))
+            .arg(tank_type_arg.clone())
+            .arg(number_arg("min-twr", "Minimum lift-off TWR, 1 by default"))
+            .arg(number_arg("min-stage-twr", "Minimum vacuum TWR at ignition of the upper stages")))
+        .subcommand(SubCommand::with_name("search")
+            .about("Searches the catalog for the best engines for each stage")
+            .arg(number_arg("delta-v", "Delta-v to reach, in m/s").required(true))
+            .arg(number_arg("payload", "Payload mass, in kg"))
+            .arg(number_arg("stages", "Number of stages, 2 by default"))
+            .arg(number_arg("max-engines", "Most engines of one type per stage, 4 by default"))
+            .arg(Arg::with_name("verniers")
+                .help("Also try each vernier on each stage")
+                .long("verniers"))
+            .arg(Arg::with_name("boosters")
+                .help("Numbers of boosters to try on the bottom stage (e.g. 2,4)")
+                .long("boosters")
+                .takes_value(true)
+                .use_delimiter(true)
+                .validator(|v| v.parse::<usize>().map(|_| ()).map_err(|e| e.to_string())))
+            .arg(number_arg("tank-fraction", "Tank dry mass per kg of propellant, 0.1 by default"))
+            .arg(tank_type_arg.clone())
+            .arg(number_arg("min-twr", "Minimum lift-off TWR, 1 by default"))
+            .arg(number_arg("min-stage-twr", "Minimum vacuum TWR at ignition of the upper stages"))
+            .arg(number_arg("max-g", "Maximum g-force of any stage"))
+            .arg(Arg::with_name("rank")
+                .help("What to rank designs by")
+                .long("rank")
+                .possible_values(&["mass", "cost", "payload-fraction"])
+                .default_value("mass"))
+            .arg(number_arg("results", "Number of designs to show, 10 by default")))
+        .subcommand(SubCommand::with_name("dispersion")
+            .about("Flies the rocket many times with engine and mass errors and shows the spread")
+            .arg(rocket_arg.clone())
+            .arg(number_arg("isp", "Standard deviation of engine Isp, as a fraction, 0.01 by default"))
+            .arg(number_arg("thrust", "Standard deviation of engine thrust, as a fraction, 0.02 by default"))
+            .arg(number_arg("residuals", "Standard deviation of propellant left at burnout, as a fraction, 0.01 by default"))
+            .arg(number_arg("dry-mass", "Standard deviation of stage dry mass, as a fraction, 0.03 by default"))
+            .arg(number_arg("samples", "Number of flights, 2000 by default"))
+            .arg(number_arg("seed", "Random seed, to repeat a run"))
+            .arg(Arg::with_name("target")
+                .help("Destination for the payload percentiles, orbit by default")
+                .long("target")
+                .short("t")
+                .takes_value(true))
+            .arg(number_arg("min-twr", "Minimum lift-off TWR, 1 by default")))
+        .subcommand(SubCommand::with_name("check")
+            .about("Checks a mission plan against engine ignitions and ullage, and flies its burns")
+            .arg(rocket_arg.clone())
+            .arg(Arg::with_name("mission")
+                .help("TOML or JSON file listing the burns of the mission")
+                .required(true)))
+        .subcommand(SubCommand::with_name("where")
+            .about("Shows where the rocket can go with its current payload")
+            .arg(rocket_arg.clone()))
+        .subcommand(SubCommand::with_name("route")
+            .about("Shows the cheapest route through a list of locations and its delta-v")
+            .arg(Arg::with_name("stops")
+                .help("Locations to visit in order, starting with the first (e.g. \"Earth Surface\")")
+                .required(true)
+                .multiple(true)
+                .min_values(2))
+            .arg(Arg::with_name("rocket")
+                .help("Check whether this rocket can fly the route")
+                .long("rocket")
+                .short("r")
+                .takes_value(true))
+            .arg(Arg::with_name("aerobrake")
+                .help("Aerobrake instead of burning wherever there is an atmosphere")
+                .long("aerobrake")))
+        .subcommand(SubCommand::with_name("map")
+            .about("Lists the locations of the delta-v map and the burns between them"))
+        .subcommand(SubCommand::with_name("ascent")
+            .about("Simulates a gravity turn to orbit and reports the losses")
+            .arg(rocket_arg.clone())
+            .arg(number_arg("target-altitude", "Altitude of the target orbit, in km"))
+            .arg(number_arg("pitch-speed", "Speed at which to start the gravity turn, in m/s"))
+            .arg(number_arg("pitch-angle", "Angle from vertical to pitch over to, in degrees"))
+            .arg(number_arg("drag-area", "Drag coefficient times cross sectional area, in m²"))
+            .arg(number_arg("surface-speed", "Eastward speed of the launch site, in m/s"))
+            .arg(number_arg("max-g", "Throttle down to stay under this many g")))
+        .subcommand(SubCommand::with_name("burn")
+            .about("Estimates the finite-burn losses of a long burn from orbit")
+            .arg(rocket_arg.clone())
+            .arg(number_arg("delta-v", "Impulsive delta-v of the maneuver, in m/s")
+                .required(true))
+            .arg(number_arg("stage", "Stage making the burn, counted from 1 at the bottom as in mission plans, the top stage by default"))
+            .arg(Arg::with_name("body")
+                .help("Body to burn around, the home planet by default")
+                .long("body")
+                .short("b")
+                .takes_value(true))
+            .arg(number_arg("periapsis", "Periapsis altitude of the start orbit, in km, low orbit by default"))
+            .arg(number_arg("apoapsis", "Apoapsis altitude of the start orbit, in km, the periapsis by default"))
+            .arg(number_arg("max-loss", "Suggest perigee kicks above this loss, in percent of the delta-v, 1 by default")))
+        .subcommand(SubCommand::with_name("engines")
+            .about("Lists engines in the catalog")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("list")
+                .about("Lists every engine with its Isp, thrust and burn time"))
+            .subcommand(SubCommand::with_name("show")
+                .about("Shows the details of a single engine")
+                .arg(Arg::with_name("name").required(true))))
+        .subcommand(SubCommand::with_name("fuels")
+            .about("Lists fuels in the catalog")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("list")
+                .about("Lists every fuel with its density")))
+        .get_matches();
+
+    if let Err(e) = run(&matches) {
+        eprintln!("{}", e);
+        process::exit(1);
     }
-    println!("{}", "-".repeat(78));
-    println!("Total: {:6.0} m/s", rocket.delta_v());
-    println!("Max G: {:10.2}", rocket.max_g_force());
-    println!("");
-    println!("");
-    print_where_rocket_can_go(&rocket);
 }
 
-const GRAVITY: f64 = 9.82;
-const DV_TO_ORBIT: f64 = 9400.0;
-const DV_TO_GTO: f64 = DV_TO_ORBIT + 2440.0;
-const DV_TO_GEO: f64 = DV_TO_GTO + 1850.0;
-const DV_TO_TLI: f64 = DV_TO_GTO + 680.0; // 3120 from orbit
-const DV_TO_LLO: f64 = DV_TO_TLI + 140.0 + 680.0;
-const DV_TO_VENUS: f64 = DV_TO_TLI + 370.0; // 3490 from orbit
-const DV_TO_VENUS_ORBIT: f64 = DV_TO_VENUS + 3800.0;
-const DV_TO_MARS: f64 = DV_TO_TLI + 480.0;
-const DV_TO_MARS_ORBIT: f64 = DV_TO_MARS + 1200.0;
-const DV_TO_MERCURY: f64 = DV_TO_VENUS + 2060.0;
-const DV_TO_JUPITER: f64 = DV_TO_MARS + 2700.0;
-
-trait Stage {
-    fn engines(&self) -> Vec<Engine>;
-    fn dry_mass(&self) -> f64;
-    fn wet_mass(&self) -> f64;
-
-    fn burn_time(&self) -> f64 {
-        self.engines().iter().map(|e| e.burn_time)
-            .max_by_key(|x| *x as u64).unwrap_or(0.0)
-    }
-
-    fn isp(&self) -> f64 {
-        let engines = self.engines();
-        engines.iter().map(|e| e.thrust).sum::<f64>() /
-            engines.iter().map(|e| e.thrust / e.isp).sum::<f64>()
-    }
-
-    /// Simple stages don't need to implement this method. It is used to
-    /// calculate delta-v when there are boosters involved. To combine multiple
-    /// simple stages, use `Rocket` instead.
-    fn next_stage(&self) -> Option<Box<Stage>> {
-        None
-    }
-
-    fn delta_v(&self) -> f64 {
-        self.isp() * (self.wet_mass() / self.dry_mass()).ln() * GRAVITY
-    }
-
-    fn max_g_force(&self) -> f64 {
-        self.engines().iter().map(|e| e.thrust * 1000.0).sum::<f64>() /
-            self.dry_mass() / GRAVITY
-    }
-
-    fn twr(&self) -> f64 {
-        self.engines().iter().map(|e| e.thrust * 1000.0).sum::<f64>() /
-            self.wet_mass() / GRAVITY
-    }
-
-    fn propellants_required(&self) -> HashMap<&'static str, f64> {
-        let mut result = HashMap::new();
-        for engine in self.engines() {
-            for (prop, amount) in engine.propellants_required() {
-                *result.entry(prop.name).or_insert(0.0) += amount;
-            }
+fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let catalog = load_catalog(matches)?;
+    let system = load_system(matches);
+
+    match matches.subcommand() {
+        ("analyze", Some(args)) => {
+            let format = match (args.value_of("format"), args.value_of("table")) {
+                (Some("json"), _) => Format::Json,
+                (Some("csv"), Some("destinations")) => Format::DestinationsCsv,
+                (Some("csv"), _) => Format::StagesCsv,
+                _ => Format::Text,
+            };
+            commands::analyze(args.value_of("rocket").unwrap(), &catalog, &system, format, number_value(args, "max-g"))
         }
-        result
-    }
-}
-
-impl<T: ?Sized + Stage> Stage for Box<T> {
-    fn engines(&self) -> Vec<Engine> { (&**self).engines() }
-    fn dry_mass(&self) -> f64 { (&**self).dry_mass() }
-    fn wet_mass(&self) -> f64 { (&**self).wet_mass() }
-    fn burn_time(&self) -> f64 { (&**self).burn_time() }
-    fn next_stage(&self) -> Option<Box<Stage>> { (&**self).next_stage() }
-    fn delta_v(&self) -> f64 { (&**self).delta_v() }
-}
-
-impl<'a, T: ?Sized + Stage> Stage for &'a T {
-    fn engines(&self) -> Vec<Engine> { (&**self).engines() }
-    fn dry_mass(&self) -> f64 { (&**self).dry_mass() }
-    fn wet_mass(&self) -> f64 { (&**self).wet_mass() }
-    fn burn_time(&self) -> f64 { (&**self).burn_time() }
-    fn next_stage(&self) -> Option<Box<Stage>> { (&**self).next_stage() }
-    fn delta_v(&self) -> f64 { (&**self).delta_v() }
-}
-
-#[derive(Debug, Clone)]
-struct SimpleStage {
-    dry_mass: f64,
-    engines: Vec<Engine>,
-}
-
-impl SimpleStage {
-    fn with_remaining_burn_time(&self, burn_time: f64) -> Self {
-        let mut new_stage = self.clone();
-        for engine in &mut new_stage.engines {
-            engine.burn_time = burn_time;
+        ("max-payload", Some(args)) => {
+            let target = PayloadTarget {
+                min_liftoff_twr: number_value(args, "min-twr").unwrap_or(1.0),
+                min_stage_twr: number_value(args, "min-stage-twr").unwrap_or(0.0),
+                tolerance: number_value(args, "tolerance").unwrap_or(1.0),
+                ..PayloadTarget::default()
+            };
+            commands::max_payload(args.value_of("rocket").unwrap(), &catalog, &system, target, args.value_of("target"))
         }
-        new_stage
-    }
-
-    fn with_verniers(mut self, vernier: Engine) -> Self {
-        let vernier = vernier.with_burn_time(self.engines[0].burn_time);
-        self.engines.push(vernier);
-        self.engines.push(vernier);
-        self
-    }
-}
-
-impl Stage for SimpleStage {
-    fn engines(&self) -> Vec<Engine> { self.engines.clone() }
-    fn dry_mass(&self) -> f64 { self.dry_mass }
-
-    fn wet_mass(&self) -> f64 {
-        self.dry_mass + self.engines.iter().map(|e| e.propellant_mass_for_full_burn()).sum::<f64>()
-    }
-}
-
-#[derive(Debug, Clone)]
-struct BoostedStage {
-    core: SimpleStage,
-    booster: SimpleStage,
-    booster_count: usize,
-}
-
-impl BoostedStage {
-    fn stage_after_booster_separation(&self) -> SimpleStage {
-        self.core.with_remaining_burn_time(self.core.burn_time() - self.booster.burn_time())
-    }
-}
-
-impl Stage for BoostedStage {
-    fn engines(&self) -> Vec<Engine> {
-        let mut engines = self.core.engines();
-        let booster_engines = self.booster.engines();
-        for _ in 0..self.booster_count {
-            engines.extend_from_slice(&booster_engines);
+        ("size", Some(args)) => {
+            let target = PayloadTarget {
+                delta_v: number_value(args, "delta-v").unwrap(),
+                min_liftoff_twr: number_value(args, "min-twr").unwrap_or(1.0),
+                min_stage_twr: number_value(args, "min-stage-twr").unwrap_or(0.0),
+                ..PayloadTarget::default()
+            };
+            commands::size(args.value_of("rocket").unwrap(), &catalog, &system, &tank_model(args), &target)
         }
-        engines
-    }
-
-    fn burn_time(&self) -> f64 {
-        self.booster.burn_time()
-    }
-
-    fn dry_mass(&self) -> f64 {
-        self.stage_after_booster_separation().wet_mass() + self.booster.dry_mass() * self.booster_count as f64
-    }
-
-    fn wet_mass(&self) -> f64 {
-        self.core.wet_mass() + self.booster.wet_mass() * self.booster_count as f64
-    }
-
-    fn next_stage(&self) -> Option<Box<Stage>> {
-        Some(Box::new(self.stage_after_booster_separation()))
-    }
-}
-
-struct StageWithPayload<T> {
-    stage: T,
-    payload_mass: f64,
-}
-
-impl<T: Stage> Stage for StageWithPayload<T> {
-    fn engines(&self) -> Vec<Engine> {
-        self.stage.engines()
-    }
-
-    fn dry_mass(&self) -> f64 {
-        self.stage.dry_mass() + self.payload_mass
-    }
-
-    fn wet_mass(&self) -> f64 {
-        self.stage.wet_mass() + self.payload_mass
-    }
-
-    fn next_stage(&self) -> Option<Box<Stage>> {
-        self.stage.next_stage().map(|s| Box::new(StageWithPayload {
-            stage: s,
-            payload_mass: self.payload_mass,
-        }) as Box<Stage>)
-    }
-
-    fn burn_time(&self) -> f64 {
-        self.stage.burn_time()
-    }
-}
-
-struct Rocket {
-    stages: Vec<Box<Stage>>,
-    payload_mass: f64,
-}
-
-impl Rocket {
-    fn stages<'a>(&'a self) -> Box<Iterator<Item=Box<Stage + 'a>> + 'a> {
-        let mut iterator = RocketStages {
-            current: None,
-            remaining: self.stages.iter(),
-            payload_mass: self.payload_mass,
-            _marker: PhantomData,
-        };
-        iterator.next();
-        Box::new(iterator)
-    }
-
-    fn delta_v(&self) -> f64 {
-        self.stages().map(|s| s.delta_v()).sum()
-    }
-
-    fn set_payload_for_target_deltav(&mut self, target_delta_v: f64) {
-        self.payload_mass = 0.0;
-        let mut last_mass = 0.0;
-        while self.delta_v() > target_delta_v {
-            last_mass = self.payload_mass;
-            if self.payload_mass < 50.0 {
-                self.payload_mass += 10.0;
-            } else if self.payload_mass < 500.0 {
-                self.payload_mass += 50.0;
-            } else {
-                self.payload_mass += 100.0;
+        ("search", Some(args)) => {
+            let defaults = SearchConfig::default();
+            let config = SearchConfig {
+                payload_mass: number_value(args, "payload").unwrap_or(0.0),
+                stage_count: number_value(args, "stages").map(|n| n as usize).unwrap_or(defaults.stage_count),
+                max_engine_count: number_value(args, "max-engines").map(|n| n as usize).unwrap_or(defaults.max_engine_count),
+                verniers: args.is_present("verniers"),
+                booster_counts: args.values_of("boosters").into_iter().flatten()
+                    .map(|v| v.parse().expect("validated by clap"))
+                    .collect(),
+                tanks: tank_model(args),
+                target: PayloadTarget {
+                    delta_v: number_value(args, "delta-v").unwrap(),
+                    min_liftoff_twr: number_value(args, "min-twr").unwrap_or(1.0),
+                    min_stage_twr: number_value(args, "min-stage-twr").unwrap_or(0.0),
+                    ..defaults.target
+                },
+                max_g_force: number_value(args, "max-g").unwrap_or(defaults.max_g_force),
+                ranking: match args.value_of("rank") {
+                    Some("cost") => Ranking::Cost,
+                    Some("payload-fraction") => Ranking::PayloadFraction,
+                    _ => Ranking::LiftoffMass,
+                },
+                results: number_value(args, "results").map(|n| n as usize).unwrap_or(defaults.results),
+            };
+            commands::search(&catalog, &config);
+            Ok(())
+        }
+        ("dispersion", Some(args)) => {
+            let defaults = Dispersion::default();
+            let dispersion = Dispersion {
+                isp: number_value(args, "isp").unwrap_or(defaults.isp),
+                thrust: number_value(args, "thrust").unwrap_or(defaults.thrust),
+                residuals: number_value(args, "residuals").unwrap_or(defaults.residuals),
+                dry_mass: number_value(args, "dry-mass").unwrap_or(defaults.dry_mass),
+                samples: number_value(args, "samples").map(|n| n as usize).unwrap_or(defaults.samples),
+                seed: number_value(args, "seed").map(|n| n as u64).unwrap_or(defaults.seed),
+            };
+            let min_liftoff_twr = number_value(args, "min-twr").unwrap_or(1.0);
+            commands::dispersion(args.value_of("rocket").unwrap(), &catalog, &system, &dispersion, args.value_of("target"), min_liftoff_twr)
+        }
+        ("check", Some(args)) => {
+            commands::check(args.value_of("rocket").unwrap(), args.value_of("mission").unwrap(), &catalog, &system)
+        }
+        ("where", Some(args)) => commands::where_rocket_can_go(args.value_of("rocket").unwrap(), &catalog, &system),
+        ("route", Some(args)) => {
+            let stops = args.values_of("stops").unwrap().collect::<Vec<_>>();
+            commands::route(&stops, args.is_present("aerobrake"), args.value_of("rocket"), &catalog, &system)
+        }
+        ("map", Some(_)) => {
+            text::print_map(&DeltaVMap::for_system(&system));
+            Ok(())
+        }
+        ("ascent", Some(args)) => {
+            let defaults = AscentConfig::default();
+            let config = AscentConfig {
+                target_altitude: number_value(args, "target-altitude").map(|km| km * 1000.0)
+                    .unwrap_or(system.home.low_orbit_altitude),
+                pitch_speed: number_value(args, "pitch-speed").unwrap_or(defaults.pitch_speed),
+                pitch_angle: number_value(args, "pitch-angle").unwrap_or(defaults.pitch_angle),
+                drag_area: number_value(args, "drag-area").unwrap_or(defaults.drag_area),
+                surface_speed: number_value(args, "surface-speed").unwrap_or(defaults.surface_speed),
+                max_g_force: number_value(args, "max-g"),
+                ..defaults
+            };
+            commands::ascent(args.value_of("rocket").unwrap(), &catalog, &system, &config)
+        }
+        ("burn", Some(args)) => {
+            let options = BurnOptions {
+                delta_v: number_value(args, "delta-v").unwrap(),
+                stage: number_value(args, "stage").map(|n| n as usize),
+                body: args.value_of("body"),
+                periapsis: number_value(args, "periapsis").map(|km| km * 1000.0),
+                apoapsis: number_value(args, "apoapsis").map(|km| km * 1000.0),
+                max_loss: number_value(args, "max-loss").unwrap_or(1.0) / 100.0,
+            };
+            commands::burn(args.value_of("rocket").unwrap(), &catalog, &system, &options)
+        }
+        ("engines", Some(args)) => match args.subcommand() {
+            ("show", Some(args)) => commands::engine(&catalog, args.value_of("name").unwrap()),
+            _ => {
+                text::print_engines(&catalog);
+                Ok(())
             }
+        },
+        ("fuels", Some(_)) => {
+            text::print_fuels(&catalog);
+            Ok(())
         }
-        self.payload_mass = last_mass;
-    }
-
-    fn max_g_force(&self) -> f64 {
-        let mut g_forces = self.stages().map(|s| s.max_g_force()).collect::<Vec<_>>();
-        g_forces.sort_by(|a, b| b.partial_cmp(a).expect("We should never get NaN here"));
-        *g_forces.first().unwrap_or(&0.0)
-    }
-
-    fn with_payload(mut self, payload: Box<Stage>) -> Self {
-        self.stages.push(payload);
-        self.payload_mass = 0.0;
-        self
-    }
-
-    fn with_payload_mass(mut self, payload_mass: f64) -> Self {
-        self.payload_mass = payload_mass;
-        self
-    }
-}
-
-struct RocketStages<'a, T> {
-    current: Option<Box<Stage + 'a>>,
-    remaining: T,
-    payload_mass: f64,
-    _marker: PhantomData<&'a ()>
-}
-
-impl<'a, T, U> Iterator for RocketStages<'a, U> where
-    T: Stage + 'a,
-    U: Iterator<Item=T> + Clone,
-{
-    type Item = Box<Stage + 'a>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let result = self.current.take();
-        self.current = result.as_ref().and_then(|s| s.next_stage())
-            .or_else(|| {
-                self.remaining.next().map(|s| {
-                    let upper_stage_weight = self.remaining.clone().map(|s| s.wet_mass()).sum::<f64>();
-                    Box::new(StageWithPayload {
-                        stage: s,
-                        payload_mass: upper_stage_weight + self.payload_mass,
-                    }) as Box<Stage>
-                })
-            });
-        result
+        _ => unreachable!("clap requires a subcommand"),
     }
 }
 
-struct BurnTime(f64);
-
-impl fmt::Display for BurnTime {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let minutes = self.0 as u64 / 60;
-        let seconds = self.0 as u64 % 60;
-        if seconds == 0 {
-            format!("{}m", minutes).fmt(fmt)
-        } else if minutes == 0 {
-            format!("{}s", seconds).fmt(fmt)
-        } else {
-            format!("{}m {}s", minutes, seconds).fmt(fmt)
+fn load_catalog(matches: &ArgMatches) -> Result<Catalog, Error> {
+    let mut catalog = Catalog::builtin();
+    // Global args are only visible on the subcommand they were passed to
+    let mut matches = Some(matches);
+    while let Some(args) = matches {
+        for path in args.values_of("catalog").into_iter().flatten() {
+            catalog.load_file(path)?;
         }
+        matches = args.subcommand().1;
     }
+    Ok(catalog)
 }
 
-fn print_where_rocket_can_go(rocket: &Rocket) {
-    let dv = rocket.delta_v();
-    if dv <= DV_TO_ORBIT {
-        println!("{}", Red.bold().paint("This rocket will not reach orbit"));
-    }
-
-    print_if_rocket_can_go_to(dv, DV_TO_GTO, "GTO");
-    print_if_rocket_can_go_to(dv, DV_TO_GEO, "GEO");
-    print_if_rocket_can_go_to(dv, DV_TO_TLI, "The Moon");
-    print_if_rocket_can_go_to(dv, DV_TO_LLO, "Lunar Orbit");
-    print_if_rocket_can_go_to(dv, DV_TO_VENUS, "Venus");
-    print_if_rocket_can_go_to(dv, DV_TO_VENUS_ORBIT, "Low Venus Orbit");
-    print_if_rocket_can_go_to(dv, DV_TO_MARS, "Mars");
-    print_if_rocket_can_go_to(dv, DV_TO_MARS_ORBIT, "Low Martian Orbit");
-    print_if_rocket_can_go_to(dv, DV_TO_MERCURY, "Mercury");
-    print_if_rocket_can_go_to(dv, DV_TO_JUPITER, "Jupiter");
-    if dv > DV_TO_GTO {
-        println!("Note: Assumes no gravity assists");
-    }
+fn number_arg<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name(name)
+        .help(help)
+        .long(name)
+        .takes_value(true)
+        .validator(|v| v.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
 }
 
-fn print_if_rocket_can_go_to(dv: f64, required_dv: f64, name: &str) {
-    if dv > required_dv * 1.05 {
-        let excess = dv - required_dv;
-        println!("{}", Blue.paint(format!("This rocket can go to {} with {:.0} m/s excess dV", name, excess)));
-    } else if dv > required_dv {
-        println!("{}", Yellow.paint(format!("This rocket can go to {} without safety margins", name)));
-    }
-}
-
-const ATLAS_DECOUPLER_MASS: f64 = 1610.0;
-
-fn print_max_payloads(rocket: &mut Rocket) {
-    print_max_payoad(rocket, DV_TO_ORBIT, "orbit");
-    print_max_payoad(rocket, DV_TO_GTO * 1.015, "GTO");
-    print_max_payoad(rocket, DV_TO_GEO * 1.015, "GEO");
-    print_max_payoad(rocket, DV_TO_TLI * 1.015, "TLI");
-    print_max_payoad(rocket, DV_TO_LLO * 1.015, "Lunar Orbit");
-    print_max_payoad(rocket, DV_TO_VENUS * 1.015, "Venus");
-    print_max_payoad(rocket, DV_TO_VENUS_ORBIT * 1.015, "Low Venus Orbit");
-    print_max_payoad(rocket, DV_TO_MARS * 1.015, "Mars");
-    print_max_payoad(rocket, DV_TO_MARS_ORBIT * 1.015, "Low Mars Orbit");
-    print_max_payoad(rocket, DV_TO_MERCURY * 1.015, "Mercury");
-    print_max_payoad(rocket, DV_TO_JUPITER * 1.015, "Jupiter");
+fn number_value(args: &ArgMatches, name: &str) -> Option<f64> {
+    args.value_of(name).map(|v| v.parse().expect("validated by clap"))
 }
 
-fn print_max_payoad(rocket: &mut Rocket, required_dv: f64, name: &str) {
-    let original_payload = rocket.payload_mass;
-    rocket.set_payload_for_target_deltav(required_dv);
-    if rocket.payload_mass > 0.0 {
-        println!("Max to {}: {}", name, rocket.payload_mass);
+fn load_system(matches: &ArgMatches) -> System {
+    let mut matches = Some(matches);
+    let mut name = None;
+    while let Some(args) = matches {
+        if args.occurrences_of("system") > 0 {
+            name = args.value_of("system");
+        }
+        matches = args.subcommand().1;
     }
-    rocket.payload_mass = original_payload;
+    let name = name.unwrap_or("rss");
+    *SYSTEMS.iter().find(|s| s.name == name).expect("validated by clap")
 }
 
-fn probe(dry_mass: f64, burn_time: f64) -> SimpleStage {
-    SimpleStage { dry_mass: dry_mass, engines: vec![THRUSTER_2.with_burn_time(burn_time)] }
+fn tank_model(args: &ArgMatches) -> TankModel {
+    match args.value_of("tank-type") {
+        Some(name) => TankModel::Type(TankType::find(name).expect("validated by clap")),
+        None => number_value(args, "tank-fraction").map(TankModel::MassFraction).unwrap_or_default(),
+    }
 }
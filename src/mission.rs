@@ -0,0 +1,544 @@
+//! Mission plans: the order in which stages are lit.
+//!
+//! A plan is a list of burns loaded from a data file. Stages are numbered
+//! from 1 at the bottom, as in the rocket design. Propellant is settled for
+//! the first burn of the plan (it's sitting on the pad) and for any burn
+//! that follows the previous one on the same stage without a coast.
+//! Everything else, including the first burn after staging, needs ullage
+//! motors or RCS on the stage.
+//!
+//! Each burn can ask for some `delta_v`, and release `drop_mass` of payload
+//! once it's done. Lighting a stage drops the stages below it, along with
+//! whatever propellant they have left.
+//!
+//! ```toml
+//! [[burns]]
+//! stage = 1
+//! delta_v = 3800.0
+//!
+//! [[burns]]
+//! stage = 2
+//! name = "Parking orbit"
+//! delta_v = 5600.0
+//!
+//! [[burns]]
+//! stage = 2
+//! name = "Transfer"
+//! coast = true
+//! delta_v = 3100.0
+//! drop_mass = 150.0
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use catalog::Catalog;
+use data_file;
+use design::{RocketDesign, StageDesign};
+use engines::Engine;
+use error::Error;
+use rocket::Rocket;
+use stage::{Stage, GRAVITY};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MissionPlan {
+    pub burns: Vec<Burn>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Burn {
+    pub stage: usize,
+    pub name: Option<String>,
+    /// Whether the rocket coasts before this burn, unsettling the propellant
+    #[serde(default)]
+    pub coast: bool,
+    #[serde(default)]
+    pub delta_v: f64,
+    /// Payload released after the burn, in kg
+    #[serde(default)]
+    pub drop_mass: f64,
+}
+
+/// The state of the rocket after a burn of a plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BurnReport {
+    /// Index of the burn in the plan
+    pub burn: usize,
+    pub stage: usize,
+    /// Delta-v the burn got, which is short of the plan if the stage ran dry
+    pub delta_v: f64,
+    pub complete: bool,
+    /// Propellant left in the stage, in kg
+    pub remaining_propellant: f64,
+    /// Delta-v the rocket has left over what the rest of the plan needs
+    pub delta_v_margin: f64,
+    /// Burns of the stage so far
+    pub ignitions_used: u32,
+    /// Fewest ignitions left on any of the stage's engines, `None` if they're
+    /// all unlimited
+    pub ignitions_left: Option<u32>,
+}
+
+/// Something that would go wrong flying a mission plan. `burn` is the index
+/// of the offending burn in the plan.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    UnknownStage { burn: usize, stage: usize },
+    /// The stage was already dropped when a stage above it was lit
+    StageJettisoned { burn: usize, stage: usize },
+    OutOfIgnitions { burn: usize, stage: usize, engine: String, ignitions: u32 },
+    NoUllage { burn: usize, stage: usize },
+    /// The burn drops more than the payload left aboard, in kg
+    DropMass { burn: usize, drop_mass: f64, payload_mass: f64 },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Issue::UnknownStage { burn, stage } => write!(
+                f,
+                "burn {}: the rocket has no stage {}",
+                burn + 1,
+                stage,
+            ),
+            Issue::StageJettisoned { burn, stage } => write!(
+                f,
+                "burn {}: stage {} was jettisoned when a later stage was lit",
+                burn + 1,
+                stage,
+            ),
+            Issue::OutOfIgnitions { burn, stage, ref engine, ignitions } => write!(
+                f,
+                "burn {}: stage {} engine `{}` only has {} ignition(s)",
+                burn + 1,
+                stage,
+                engine,
+                ignitions,
+            ),
+            Issue::NoUllage { burn, stage } => write!(
+                f,
+                "burn {}: stage {} needs ullage after a coast, but has no ullage motors left or RCS",
+                burn + 1,
+                stage,
+            ),
+            Issue::DropMass { burn, drop_mass, payload_mass } => write!(
+                f,
+                "burn {}: drops {} kg, but only {} kg of payload is left",
+                burn + 1,
+                drop_mass,
+                payload_mass,
+            ),
+        }
+    }
+}
+
+impl MissionPlan {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        data_file::read(path.as_ref())
+    }
+
+    /// Checks the plan against the engines' ignitions and ullage needs, and
+    /// the payload it drops. Fails if the design refers to unknown engines.
+    pub fn validate(&self, design: &RocketDesign, catalog: &Catalog) -> Result<Vec<Issue>, Error> {
+        let mut issues = Vec::new();
+        let mut ignitions = HashMap::new();
+        let mut ullage_motors = design.stages.iter().map(|s| s.ullage_motors).collect::<Vec<_>>();
+        let mut highest_stage = 0;
+        let mut payload_mass = design.payload_mass;
+
+        for (i, burn) in self.burns.iter().enumerate() {
+            let stage = match design.stages.get(burn.stage.wrapping_sub(1)) {
+                Some(stage) => stage,
+                None => {
+                    issues.push(Issue::UnknownStage { burn: i, stage: burn.stage });
+                    continue;
+                }
+            };
+            if burn.stage < highest_stage {
+                issues.push(Issue::StageJettisoned { burn: i, stage: burn.stage });
+                continue;
+            }
+            // Staging unsettles the propellant of the stage being lit
+            let settled = highest_stage == 0 || (burn.stage == highest_stage && !burn.coast);
+            highest_stage = burn.stage;
+
+            let engines = stage_engines(stage, catalog)
+                .map_err(|e| e.in_stage(burn.stage - 1))?;
+            for engine in &engines {
+                let lit = ignitions.entry((burn.stage, engine.name.to_string())).or_insert(0);
+                *lit += 1;
+                if let Some(limit) = engine.ignitions {
+                    if *lit > limit {
+                        issues.push(Issue::OutOfIgnitions {
+                            burn: i,
+                            stage: burn.stage,
+                            engine: engine.name.to_string(),
+                            ignitions: limit,
+                        });
+                    }
+                }
+            }
+
+            if !settled && engines.iter().any(|e| e.ullage) && !stage.rcs {
+                let motors = &mut ullage_motors[burn.stage - 1];
+                if *motors > 0 {
+                    *motors -= 1;
+                } else {
+                    issues.push(Issue::NoUllage { burn: i, stage: burn.stage });
+                }
+            }
+
+            if burn.drop_mass > payload_mass {
+                issues.push(Issue::DropMass { burn: i, drop_mass: burn.drop_mass, payload_mass });
+            }
+            payload_mass = (payload_mass - burn.drop_mass).max(0.0);
+        }
+
+        Ok(issues)
+    }
+
+    /// Flies the plan on `rocket`, which is `design` as built, following
+    /// the propellant and mass of each stage. Burns on stages which don't
+    /// exist or were already dropped are left out, see `validate`. Fails if
+    /// the design refers to unknown engines.
+    pub fn fly(&self, design: &RocketDesign, rocket: &Rocket, catalog: &Catalog) -> Result<Vec<BurnReport>, Error> {
+        let ignition_limits = design.stages.iter().enumerate()
+            .map(|(i, stage)| {
+                let engines = stage_engines(stage, catalog).map_err(|e| e.in_stage(i))?;
+                Ok(engines.iter().filter_map(|e| e.ignitions).min())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut flight = Flight::new(rocket);
+        let mut ignitions = vec![0; rocket.stages.len()];
+        let mut reports = Vec::new();
+
+        for (i, burn) in self.burns.iter().enumerate() {
+            let stage = match flight.stage_for(burn) {
+                Some(stage) => stage,
+                None => continue,
+            };
+            let delta_v = flight.burn(stage, burn.delta_v);
+            flight.mass -= burn.drop_mass;
+            ignitions[stage] += 1;
+
+            reports.push(BurnReport {
+                burn: i,
+                stage: burn.stage,
+                delta_v,
+                complete: delta_v >= burn.delta_v * (1.0 - 1e-9),
+                remaining_propellant: flight.stages[stage].remaining_propellant(),
+                delta_v_margin: self.margin_after(flight.clone(), i + 1),
+                ignitions_used: ignitions[stage],
+                ignitions_left: ignition_limits[stage].map(|limit| limit.saturating_sub(ignitions[stage])),
+            });
+        }
+        Ok(reports)
+    }
+
+    /// Delta-v left after flying the burns from `next` on, dropping their
+    /// payloads, less whatever those burns fell short by
+    fn margin_after(&self, mut flight: Flight, next: usize) -> f64 {
+        let mut shortfall = 0.0;
+        for burn in &self.burns[next..] {
+            if let Some(stage) = flight.stage_for(burn) {
+                shortfall += burn.delta_v - flight.burn(stage, burn.delta_v);
+                flight.mass -= burn.drop_mass;
+            }
+        }
+        flight.remaining_delta_v() - shortfall
+    }
+}
+
+/// The rocket partway through a mission
+#[derive(Clone)]
+struct Flight {
+    stages: Vec<StageState>,
+    /// Index of the lowest stage still attached
+    stage: usize,
+    /// Mass of everything still attached
+    mass: f64,
+}
+
+/// One phase of a stage's burn (see `Stage::next_stage`)
+#[derive(Clone)]
+struct Phase {
+    exhaust_velocity: f64,
+    propellant: f64,
+    /// Mass dropped once the phase is done
+    dropped_after: f64,
+}
+
+#[derive(Clone)]
+struct StageState {
+    phases: Vec<Phase>,
+    phase: usize,
+    /// Mass of the stage as it is now
+    mass: f64,
+}
+
+impl Phase {
+    fn new(phase: &dyn Stage, next: Option<&dyn Stage>) -> Self {
+        Phase {
+            exhaust_velocity: phase.isp() * GRAVITY,
+            propellant: phase.wet_mass() - phase.dry_mass(),
+            dropped_after: next.map(|n| phase.dry_mass() - n.wet_mass()).unwrap_or(0.0),
+        }
+    }
+}
+
+impl StageState {
+    fn new(stage: &dyn Stage) -> Self {
+        let mut next = stage.next_stage();
+        let mut phases = vec![Phase::new(stage, next.as_deref())];
+        while let Some(current) = next {
+            next = current.next_stage();
+            phases.push(Phase::new(&*current, next.as_deref()));
+        }
+        StageState { phases, phase: 0, mass: stage.wet_mass() }
+    }
+
+    fn remaining_propellant(&self) -> f64 {
+        self.phases[self.phase..].iter().map(|p| p.propellant).sum()
+    }
+}
+
+impl Flight {
+    fn new(rocket: &Rocket) -> Self {
+        let stages = rocket.stages.iter().map(|s| StageState::new(&**s)).collect::<Vec<_>>();
+        let mass = stages.iter().map(|s| s.mass).sum::<f64>() + rocket.payload_mass;
+        Flight { stages, stage: 0, mass }
+    }
+
+    /// Index of the stage `burn` lights, `None` if there's no such stage
+    /// or it was already dropped
+    fn stage_for(&self, burn: &Burn) -> Option<usize> {
+        let stage = burn.stage.wrapping_sub(1);
+        if stage < self.stages.len() && stage >= self.stage {
+            Some(stage)
+        } else {
+            None
+        }
+    }
+
+    /// Burns `stage` for up to `delta_v`, dropping the stages below it
+    /// first. Returns the delta-v it got.
+    fn burn(&mut self, stage: usize, delta_v: f64) -> f64 {
+        while self.stage < stage {
+            self.mass -= self.stages[self.stage].mass;
+            self.stage += 1;
+        }
+
+        let state = &mut self.stages[stage];
+        let mut achieved = 0.0;
+        while state.phase < state.phases.len() {
+            let phase = &mut state.phases[state.phase];
+            let available = phase.exhaust_velocity * (self.mass / (self.mass - phase.propellant)).ln();
+            if delta_v - achieved <= available {
+                let used = self.mass * (1.0 - (-(delta_v - achieved) / phase.exhaust_velocity).exp());
+                phase.propellant -= used;
+                self.mass -= used;
+                state.mass -= used;
+                return delta_v;
+            }
+            achieved += available;
+            self.mass -= phase.propellant + phase.dropped_after;
+            state.mass -= phase.propellant + phase.dropped_after;
+            phase.propellant = 0.0;
+            if state.phase + 1 == state.phases.len() {
+                break;
+            }
+            state.phase += 1;
+        }
+        achieved
+    }
+
+    /// Delta-v from burning everything left
+    fn remaining_delta_v(mut self) -> f64 {
+        (self.stage..self.stages.len())
+            .map(|stage| self.burn(stage, f64::INFINITY))
+            .sum()
+    }
+}
+
+/// Each distinct engine lit by a burn of the stage. Boosters only burn once,
+/// with the core, so they're left out.
+fn stage_engines(stage: &StageDesign, catalog: &Catalog) -> Result<Vec<Engine>, Error> {
+    let mut result = Vec::<Engine>::new();
+    for name in stage.engines.iter().map(|e| &e.name).chain(&stage.verniers) {
+        let engine = catalog.engine(name).ok_or_else(|| Error::UnknownEngine(name.clone()))?;
+        if !result.iter().any(|e| e.name == engine.name) {
+            result.push(engine.clone());
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engines::BELL_8081;
+    use toml;
+
+    fn validate(design: &str, plan: &str) -> Vec<Issue> {
+        let design = toml::from_str::<RocketDesign>(design).unwrap();
+        let plan = toml::from_str::<MissionPlan>(plan).unwrap();
+        plan.validate(&design, &Catalog::builtin()).unwrap()
+    }
+
+    fn fly(design: &str, plan: &str) -> Vec<BurnReport> {
+        let catalog = Catalog::builtin();
+        let design = toml::from_str::<RocketDesign>(design).unwrap();
+        let plan = toml::from_str::<MissionPlan>(plan).unwrap();
+        let rocket = design.build(&catalog).unwrap();
+        plan.fly(&design, &rocket, &catalog).unwrap()
+    }
+
+    const AGENA: &str = r#"
+        payload_mass = 1000.0
+
+        [[stages]]
+        dry_mass = 500.0
+        engines = [{ name = "Bell 8081 (XLR81-BA-7, Agena B)" }]
+    "#;
+
+    #[test]
+    fn burns_follow_the_rocket_equation() {
+        let reports = fly(AGENA, "[[burns]]\nstage = 1\ndelta_v = 1000.0\n");
+
+        let exhaust_velocity = BELL_8081.isp * GRAVITY;
+        let propellant = BELL_8081.propellant_mass_for_full_burn();
+        let start = 1500.0 + propellant;
+        let after = start * (-1000.0 / exhaust_velocity).exp();
+        assert_eq!(1, reports.len());
+        assert!(reports[0].complete);
+        assert!((reports[0].remaining_propellant - (propellant - (start - after))).abs() < 1e-6);
+        assert!((reports[0].delta_v_margin - exhaust_velocity * (after / 1500.0).ln()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn margin_counts_later_drops() {
+        let plan = r#"
+            [[burns]]
+            stage = 1
+            delta_v = 1000.0
+
+            [[burns]]
+            stage = 1
+            delta_v = 1000.0
+            drop_mass = 400.0
+        "#;
+        let reports = fly(AGENA, plan);
+
+        // Whatever is left once the payload is gone, seen from either burn
+        let exhaust_velocity = BELL_8081.isp * GRAVITY;
+        let start = 1500.0 + BELL_8081.propellant_mass_for_full_burn();
+        let end = start * (-2000.0 / exhaust_velocity).exp() - 400.0;
+        let margin = exhaust_velocity * (end / 1100.0).ln();
+        assert!((reports[0].delta_v_margin - margin).abs() < 1e-6);
+        assert!((reports[1].delta_v_margin - margin).abs() < 1e-6);
+    }
+
+    #[test]
+    fn boosters_dont_count_against_ignitions() {
+        let design = r#"
+            [[stages]]
+            dry_mass = 500.0
+            engines = [{ name = "Bell 8081 (XLR81-BA-7, Agena B)" }]
+
+            [stages.boosters]
+            count = 2
+            dry_mass = 200.0
+            engines = [{ name = "Castor 1" }]
+        "#;
+        let plan = "[[burns]]\nstage = 1\ndelta_v = 100.0\n\n[[burns]]\nstage = 1\ndelta_v = 100.0\n";
+        let reports = fly(design, plan);
+        let left = reports.iter().map(|r| r.ignitions_left).collect::<Vec<_>>();
+        assert_eq!(vec![Some(1), Some(0)], left);
+    }
+
+    #[test]
+    fn drops_are_limited_to_the_payload() {
+        let plan = r#"
+            [[burns]]
+            stage = 1
+            delta_v = 100.0
+            drop_mass = 600.0
+
+            [[burns]]
+            stage = 1
+            delta_v = 100.0
+            drop_mass = 500.0
+        "#;
+        let issues = validate(AGENA, plan);
+        assert_eq!(vec![Issue::DropMass { burn: 1, drop_mass: 500.0, payload_mass: 400.0 }], issues);
+    }
+
+    /// A Castor 1 under an Agena B with `upper_stage` settings
+    fn two_stages(upper_stage: &str) -> String {
+        format!(r#"
+            [[stages]]
+            dry_mass = 300.0
+            engines = [{{ name = "Castor 1" }}]
+
+            [[stages]]
+            dry_mass = 500.0
+            engines = [{{ name = "Bell 8081 (XLR81-BA-7, Agena B)" }}]
+            {}
+        "#, upper_stage)
+    }
+
+    /// A burn for each `(stage, coast)`
+    fn plan(burns: &[(usize, bool)]) -> String {
+        burns.iter()
+            .map(|&(stage, coast)| format!("[[burns]]\nstage = {}\ncoast = {}\ndelta_v = 100.0\n", stage, coast))
+            .collect()
+    }
+
+    #[test]
+    fn burns_need_a_stage_still_aboard() {
+        let issues = validate(&two_stages("rcs = true"), &plan(&[(1, false), (3, false), (0, false), (2, false), (1, false)]));
+        assert_eq!(vec![
+            Issue::UnknownStage { burn: 1, stage: 3 },
+            Issue::UnknownStage { burn: 2, stage: 0 },
+            Issue::StageJettisoned { burn: 4, stage: 1 },
+        ], issues);
+    }
+
+    #[test]
+    fn engines_run_out_of_ignitions() {
+        // The Agena B only lights twice, with ullage motors for each burn
+        let issues = validate(&two_stages("ullage_motors = 3"), &plan(&[(1, false), (2, false), (2, true), (2, true)]));
+        assert_eq!(vec![Issue::OutOfIgnitions {
+            burn: 3,
+            stage: 2,
+            engine: BELL_8081.name.to_string(),
+            ignitions: 2,
+        }], issues);
+    }
+
+    #[test]
+    fn staging_unsettles_the_propellant() {
+        let issues = validate(&two_stages(""), &plan(&[(1, false), (2, false)]));
+        assert_eq!(vec![Issue::NoUllage { burn: 1, stage: 2 }], issues);
+
+        let issues = validate(&two_stages("ullage_motors = 1"), &plan(&[(1, false), (2, false)]));
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn coasts_need_ullage_motors_or_rcs() {
+        // One motor for staging, one after the coast
+        let burns = plan(&[(1, false), (2, false), (2, true)]);
+        assert!(validate(&two_stages("ullage_motors = 2"), &burns).is_empty());
+        assert_eq!(vec![Issue::NoUllage { burn: 2, stage: 2 }], validate(&two_stages("ullage_motors = 1"), &burns));
+        assert!(validate(&two_stages("rcs = true"), &burns).is_empty());
+    }
+
+    #[test]
+    fn the_first_burn_is_settled_on_the_pad() {
+        assert!(validate(AGENA, &plan(&[(1, true)])).is_empty());
+    }
+}
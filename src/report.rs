@@ -0,0 +1,232 @@
+//! Machine readable summaries of a rocket's performance.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use serde_json;
+
+use bodies::System;
+use destinations::{destinations, SAFETY_MARGIN};
+use engines::SEA_LEVEL;
+use error::Error;
+use rocket::Rocket;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub stages: Vec<StageReport>,
+    pub delta_v: f64,
+    pub liftoff_twr: f64,
+    pub max_g_force: f64,
+    /// Chance that every engine works, see `Rocket::reliability`
+    pub reliability: f64,
+    pub reaches_orbit: bool,
+    pub destinations: Vec<DestinationReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageReport {
+    /// Counted from 1 at the bottom, see `StageNumber`
+    pub stage: usize,
+    /// From 0, `None` if the stage burns in a single phase
+    pub phase: Option<usize>,
+    pub delta_v: f64,
+    pub sea_level_delta_v: f64,
+    pub wet_mass: f64,
+    pub dry_mass: f64,
+    /// At sea level for the first stage on the pad, in vacuum for the rest
+    pub start_twr: f64,
+    pub end_twr: f64,
+    pub burn_time: f64,
+    /// Litres of each propellant burned by this stage
+    pub propellants: BTreeMap<String, f64>,
+}
+
+/// A destination the rocket has enough delta-v to reach
+#[derive(Debug, Clone, Serialize)]
+pub struct DestinationReport {
+    pub name: String,
+    pub required_delta_v: f64,
+    pub excess_delta_v: f64,
+    pub safety_margin: bool,
+}
+
+impl Report {
+    pub fn new(rocket: &Rocket, system: &System) -> Self {
+        let stages = rocket.stage_numbers().into_iter().zip(rocket.stages()).enumerate()
+            .map(|(i, (number, stage))| StageReport {
+                stage: number.stage,
+                phase: number.phase,
+                delta_v: stage.delta_v(),
+                sea_level_delta_v: stage.delta_v_at(SEA_LEVEL),
+                wet_mass: stage.wet_mass(),
+                dry_mass: stage.dry_mass(),
+                start_twr: if i == 0 { stage.twr() } else { stage.vacuum_twr() },
+                end_twr: stage.max_g_force(),
+                burn_time: stage.burn_time(),
+                propellants: stage.propellants_required().into_iter().collect(),
+            })
+            .collect();
+        let delta_v = rocket.delta_v();
+        let destinations = destinations(system).into_iter()
+            .filter(|d| delta_v > d.delta_v)
+            .map(|d| DestinationReport {
+                name: d.name,
+                required_delta_v: d.delta_v,
+                excess_delta_v: delta_v - d.delta_v,
+                safety_margin: delta_v > d.delta_v * SAFETY_MARGIN,
+            })
+            .collect();
+
+        Report {
+            stages,
+            delta_v,
+            liftoff_twr: rocket.liftoff_twr(),
+            max_g_force: rocket.max_g_force(),
+            reliability: rocket.reliability(),
+            reaches_orbit: delta_v > system.launch_delta_v,
+            destinations,
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, out: W) -> Result<(), Error> {
+        serde_json::to_writer_pretty(out, self)?;
+        Ok(())
+    }
+
+    /// Writes a table of stages, with a column for each propellant used by
+    /// any stage.
+    pub fn write_stages_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let propellants = self.stages.iter()
+            .flat_map(|s| s.propellants.keys())
+            .collect::<BTreeSet<_>>();
+
+        write!(out, "stage,phase,delta_v,sea_level_delta_v,wet_mass,dry_mass,start_twr,end_twr,burn_time")?;
+        for name in &propellants {
+            write!(out, ",{}", csv_field(name))?;
+        }
+        writeln!(out)?;
+        for stage in &self.stages {
+            write!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                stage.stage,
+                stage.phase.map(|p| p.to_string()).unwrap_or_default(),
+                stage.delta_v,
+                stage.sea_level_delta_v,
+                stage.wet_mass,
+                stage.dry_mass,
+                stage.start_twr,
+                stage.end_twr,
+                stage.burn_time,
+            )?;
+            for name in &propellants {
+                write!(out, ",{}", stage.propellants.get(*name).cloned().unwrap_or(0.0))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a table of reachable destinations.
+    pub fn write_destinations_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "destination,required_delta_v,excess_delta_v,safety_margin")?;
+        for destination in &self.destinations {
+            writeln!(
+                out,
+                "{},{},{},{}",
+                csv_field(&destination.name),
+                destination.required_delta_v,
+                destination.excess_delta_v,
+                destination.safety_margin,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bodies::rss;
+    use catalog::Catalog;
+    use design::RocketDesign;
+    use serde_json::Value;
+    use toml;
+
+    fn atlas_agena() -> Rocket {
+        let design: RocketDesign = toml::from_str(include_str!("../examples/atlas_agena.toml")).unwrap();
+        design.build(&Catalog::builtin()).unwrap()
+    }
+
+    #[test]
+    fn first_stage_starts_at_sea_level() {
+        let rocket = atlas_agena();
+        let report = Report::new(&rocket, &rss::SYSTEM);
+        let stages = rocket.stages().collect::<Vec<_>>();
+
+        assert_eq!(report.liftoff_twr, report.stages[0].start_twr);
+        assert!(stages[0].twr() < stages[0].vacuum_twr());
+        assert_eq!(stages[1].vacuum_twr(), report.stages[1].start_twr);
+    }
+
+    #[test]
+    fn json_lists_every_phase_and_destination() {
+        let report = Report::new(&atlas_agena(), &rss::SYSTEM);
+        let mut out = Vec::new();
+        report.write_json(&mut out).unwrap();
+        let json: Value = serde_json::from_slice(&out).unwrap();
+
+        let stages = json["stages"].as_array().unwrap();
+        let numbers = stages.iter().map(|s| (s["stage"].clone(), s["phase"].clone())).collect::<Vec<_>>();
+        assert_eq!(vec![(Value::from(1), Value::from(0)), (Value::from(1), Value::from(1)), (Value::from(2), Value::Null)], numbers);
+        assert!((json["delta_v"].as_f64().unwrap() - report.delta_v).abs() < 1e-6);
+        assert_eq!(Value::from("Low Earth Orbit"), json["destinations"][0]["name"]);
+        assert_eq!(report.destinations.len(), json["destinations"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn stages_csv_has_a_row_per_phase() {
+        let report = Report::new(&atlas_agena(), &rss::SYSTEM);
+        let mut out = Vec::new();
+        report.write_stages_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert!(lines[0].starts_with("stage,phase,delta_v,sea_level_delta_v,wet_mass,dry_mass,start_twr,end_twr,burn_time,"), "{}", lines[0]);
+        assert_eq!(1 + report.stages.len(), lines.len());
+        assert!(lines[1].starts_with("1,0,"), "{}", lines[1]);
+        assert!(lines[3].starts_with("2,,"), "{}", lines[3]);
+        let columns = lines[0].split(',').count();
+        assert!(lines.iter().all(|l| l.split(',').count() == columns), "{}", csv);
+    }
+
+    #[test]
+    fn destinations_csv_has_a_row_per_destination() {
+        let report = Report::new(&atlas_agena(), &rss::SYSTEM);
+        let mut out = Vec::new();
+        report.write_destinations_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!("destination,required_delta_v,excess_delta_v,safety_margin", lines[0]);
+        assert_eq!(1 + report.destinations.len(), lines.len());
+        let leo = &report.destinations[0];
+        let row = format!("Low Earth Orbit,{},{},{}", leo.required_delta_v, leo.excess_delta_v, leo.safety_margin);
+        assert_eq!(row, lines[1]);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!("LqdOxygen", csv_field("LqdOxygen"));
+        assert_eq!("\"Mars, low orbit\"", csv_field("Mars, low orbit"));
+        assert_eq!("\"the \"\"Moon\"\"\"", csv_field("the \"Moon\""));
+    }
+}
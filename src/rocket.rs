@@ -0,0 +1,338 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use stage::{Stage, StageWithPayload};
+
+/// Requirements for `Rocket::max_payload` and `sizing::size_stages`. TWR minimums of zero disable the
+/// check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PayloadTarget {
+    pub delta_v: f64,
+    /// Minimum TWR on the pad, using sea level thrust
+    pub min_liftoff_twr: f64,
+    /// Minimum vacuum TWR at ignition of every stage after the first
+    pub min_stage_twr: f64,
+    /// How close to the true maximum the result must be, in kg
+    pub tolerance: f64,
+}
+
+impl Default for PayloadTarget {
+    fn default() -> Self {
+        PayloadTarget {
+            delta_v: 0.0,
+            min_liftoff_twr: 0.0,
+            min_stage_twr: 0.0,
+            tolerance: 1.0,
+        }
+    }
+}
+
+/// The constraint which stops a heavier payload
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    DeltaV,
+    LiftoffTwr,
+    StageTwr(StageNumber),
+}
+
+/// Which stage an entry of `Rocket::stages` belongs to. Stages are numbered
+/// from 1 at the bottom, as in mission plans, and stages burning in several
+/// phases have them lettered from `a`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageNumber {
+    pub stage: usize,
+    /// From 0, `None` if the stage burns in a single phase
+    pub phase: Option<usize>,
+}
+
+impl fmt::Display for StageNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.stage)?;
+        if let Some(phase) = self.phase {
+            write!(f, "{}", (b'a' + phase as u8) as char)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxPayload {
+    pub payload_mass: f64,
+    /// `None` if the payload could grow without bound
+    pub limit: Option<Limit>,
+}
+
+/// A stack of stages, listed from the bottom up, topped by a payload.
+pub struct Rocket {
+    pub stages: Vec<Box<dyn Stage>>,
+    pub payload_mass: f64,
+}
+
+impl Rocket {
+    /// Every stage the rocket will fly, from the bottom up, each carrying
+    /// the stages above it and the payload. Stages with boosters produce
+    /// one entry for each phase of their burn.
+    pub fn stages(&self) -> Box<dyn Iterator<Item=Box<dyn Stage + '_>> + '_> {
+        self.stages_with_payload(self.payload_mass)
+    }
+
+    /// The number of each entry of `stages`
+    pub fn stage_numbers(&self) -> Vec<StageNumber> {
+        let mut numbers = Vec::new();
+        for (i, stage) in self.stages.iter().enumerate() {
+            let mut phases = 1;
+            let mut next = stage.next_stage();
+            while let Some(stage) = next {
+                phases += 1;
+                next = stage.next_stage();
+            }
+            numbers.extend((0..phases).map(|phase| StageNumber {
+                stage: i + 1,
+                phase: if phases > 1 { Some(phase) } else { None },
+            }));
+        }
+        numbers
+    }
+
+    fn stages_with_payload(&self, payload_mass: f64) -> Box<dyn Iterator<Item=Box<dyn Stage + '_>> + '_> {
+        let mut iterator = RocketStages {
+            current: None,
+            remaining: self.stages.iter(),
+            payload_mass,
+            _marker: PhantomData,
+        };
+        iterator.next();
+        Box::new(iterator)
+    }
+
+    pub fn delta_v(&self) -> f64 {
+        self.stages().map(|s| s.delta_v()).sum()
+    }
+
+    /// TWR on the pad, using sea level thrust.
+    pub fn liftoff_twr(&self) -> f64 {
+        self.stages().next().map(|s| s.twr()).unwrap_or(0.0)
+    }
+
+    pub fn set_payload_for_target_deltav(&mut self, target_delta_v: f64) {
+        let target = PayloadTarget { delta_v: target_delta_v, ..PayloadTarget::default() };
+        self.payload_mass = self.max_payload(&target).map(|m| m.payload_mass).unwrap_or(0.0);
+    }
+
+    /// The heaviest payload which still meets `target`, found by bisection.
+    /// `None` if the rocket can't meet it even without a payload.
+    pub fn max_payload(&self, target: &PayloadTarget) -> Option<MaxPayload> {
+        if self.limit_at(0.0, target).is_some() {
+            return None;
+        }
+
+        // Every constraint gets worse as the payload grows, so keep doubling
+        // until one fails to bracket the answer
+        let mut low = 0.0;
+        let mut high = self.stages.iter().map(|s| s.wet_mass()).sum::<f64>().max(target.tolerance);
+        let mut limit = loop {
+            match self.limit_at(high, target) {
+                Some(limit) => break limit,
+                None if high > 1e12 => return Some(MaxPayload { payload_mass: high, limit: None }),
+                None => {
+                    low = high;
+                    high *= 2.0;
+                }
+            }
+        };
+
+        while high - low > target.tolerance {
+            let middle = (low + high) / 2.0;
+            match self.limit_at(middle, target) {
+                Some(l) => {
+                    high = middle;
+                    limit = l;
+                }
+                None => low = middle,
+            }
+        }
+
+        Some(MaxPayload { payload_mass: low, limit: Some(limit) })
+    }
+
+    /// The first constraint of `target` the rocket fails with the given
+    /// payload
+    fn limit_at(&self, payload_mass: f64, target: &PayloadTarget) -> Option<Limit> {
+        if let Some(limit) = self.twr_limit_at(payload_mass, target, true) {
+            return Some(limit);
+        }
+        if self.stages_with_payload(payload_mass).map(|s| s.delta_v()).sum::<f64>() < target.delta_v {
+            return Some(Limit::DeltaV);
+        }
+        None
+    }
+
+    /// The first TWR minimum of `target` the rocket fails with its payload.
+    /// Stages are checked at ignition, not in the later phases of their
+    /// burn. Unless `lifts_off`, the bottom stage is checked as an upper
+    /// stage, for rockets which will sit on more stages.
+    pub fn twr_limit(&self, target: &PayloadTarget, lifts_off: bool) -> Option<Limit> {
+        self.twr_limit_at(self.payload_mass, target, lifts_off)
+    }
+
+    fn twr_limit_at(&self, payload_mass: f64, target: &PayloadTarget, lifts_off: bool) -> Option<Limit> {
+        let ignitions = self.stage_numbers().into_iter()
+            .zip(self.stages_with_payload(payload_mass))
+            .filter(|&(number, _)| number.phase.unwrap_or(0) == 0);
+        for (number, stage) in ignitions {
+            if number.stage == 1 && lifts_off {
+                if stage.twr() < target.min_liftoff_twr {
+                    return Some(Limit::LiftoffTwr);
+                }
+            } else if stage.vacuum_twr() < target.min_stage_twr {
+                return Some(Limit::StageTwr(number));
+            }
+        }
+        None
+    }
+
+    /// Chance that every stage works. Unlike `stages`, boosted stages are
+    /// counted once, so their core engines aren't lit twice.
+    pub fn reliability(&self) -> f64 {
+        self.stages.iter().map(|s| s.reliability()).product()
+    }
+
+    pub fn max_g_force(&self) -> f64 {
+        let mut g_forces = self.stages().map(|s| s.max_g_force()).collect::<Vec<_>>();
+        g_forces.sort_by(|a, b| b.partial_cmp(a).expect("We should never get NaN here"));
+        *g_forces.first().unwrap_or(&0.0)
+    }
+
+    pub fn with_payload(mut self, payload: Box<dyn Stage>) -> Self {
+        self.stages.push(payload);
+        self.payload_mass = 0.0;
+        self
+    }
+
+    pub fn with_payload_mass(mut self, payload_mass: f64) -> Self {
+        self.payload_mass = payload_mass;
+        self
+    }
+}
+
+/// Iterator returned by `Rocket::stages`.
+pub struct RocketStages<'a, T> {
+    current: Option<Box<dyn Stage + 'a>>,
+    remaining: T,
+    payload_mass: f64,
+    _marker: PhantomData<&'a ()>
+}
+
+impl<'a, T, U> Iterator for RocketStages<'a, U> where
+    T: Stage + 'a,
+    U: Iterator<Item=T> + Clone,
+{
+    type Item = Box<dyn Stage + 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.current.take();
+        self.current = result.as_ref().and_then(|s| s.next_stage())
+            .or_else(|| {
+                self.remaining.next().map(|s| {
+                    let upper_stage_weight = self.remaining.clone().map(|s| s.wet_mass()).sum::<f64>();
+                    Box::new(StageWithPayload {
+                        stage: s,
+                        payload_mass: upper_stage_weight + self.payload_mass,
+                    }) as Box<dyn Stage>
+                })
+            });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engines::{BELL_8081, CASTOR_1, H1, LR105_NA_5, SEA_LEVEL, VACUUM};
+    use stage::{BoostedStage, BoosterGroup, SimpleStage, GRAVITY};
+
+    fn agena() -> SimpleStage {
+        SimpleStage { dry_mass: 700.0, engines: vec![BELL_8081] }
+    }
+
+    /// A core lit with two boosters, which burn out first
+    fn boosted() -> BoostedStage {
+        BoostedStage {
+            core: SimpleStage { dry_mass: 3000.0, engines: vec![LR105_NA_5] },
+            boosters: vec![BoosterGroup {
+                booster: SimpleStage { dry_mass: 300.0, engines: vec![CASTOR_1] },
+                count: 2,
+                ignition_time: 0.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn payload_follows_the_rocket_equation() {
+        let rocket = Rocket { stages: vec![Box::new(agena())], payload_mass: 0.0 };
+        let target = PayloadTarget { delta_v: 2000.0, tolerance: 0.1, ..PayloadTarget::default() };
+        let max = rocket.max_payload(&target).unwrap();
+
+        // (wet + p) / (700 + p) = R
+        let wet = agena().wet_mass();
+        let mass_ratio = (2000.0 / (BELL_8081.isp * GRAVITY)).exp();
+        let payload = (wet - mass_ratio * 700.0) / (mass_ratio - 1.0);
+        assert_eq!(Some(Limit::DeltaV), max.limit);
+        assert!(max.payload_mass <= payload && max.payload_mass > payload - 0.1);
+    }
+
+    #[test]
+    fn payload_limited_by_liftoff_twr() {
+        let stage = SimpleStage { dry_mass: 5000.0, engines: vec![H1] };
+        let wet = stage.wet_mass();
+        let rocket = Rocket { stages: vec![Box::new(stage)], payload_mass: 0.0 };
+        let target = PayloadTarget { min_liftoff_twr: 1.2, tolerance: 0.1, ..PayloadTarget::default() };
+        let max = rocket.max_payload(&target).unwrap();
+
+        // thrust = 1.2 (wet + p) g
+        let payload = H1.thrust_at(SEA_LEVEL) * 1000.0 / GRAVITY / 1.2 - wet;
+        assert_eq!(Some(Limit::LiftoffTwr), max.limit);
+        assert!(max.payload_mass <= payload && max.payload_mass > payload - 0.1);
+    }
+
+    #[test]
+    fn payload_limited_by_upper_stage_twr() {
+        let rocket = Rocket { stages: vec![Box::new(boosted()), Box::new(agena())], payload_mass: 0.0 };
+        let target = PayloadTarget { min_stage_twr: 0.5, tolerance: 0.1, ..PayloadTarget::default() };
+        let max = rocket.max_payload(&target).unwrap();
+
+        let payload = BELL_8081.thrust_at(VACUUM) * 1000.0 / GRAVITY / 0.5 - agena().wet_mass();
+        assert_eq!(Some(Limit::StageTwr(StageNumber { stage: 2, phase: None })), max.limit);
+        assert!(max.payload_mass <= payload && max.payload_mass > payload - 0.1);
+    }
+
+    #[test]
+    fn later_phases_dont_need_the_stage_twr() {
+        // The sustainer is far too weak to light on its own
+        let boosted = BoostedStage {
+            core: SimpleStage { dry_mass: 20_000.0, engines: vec![BELL_8081] },
+            boosters: vec![BoosterGroup {
+                booster: SimpleStage { dry_mass: 3000.0, engines: vec![H1, H1] },
+                count: 2,
+                ignition_time: 0.0,
+            }],
+        };
+        let rocket = Rocket { stages: vec![Box::new(boosted), Box::new(agena())], payload_mass: 0.0 };
+        let target = PayloadTarget { min_stage_twr: 0.5, tolerance: 0.1, ..PayloadTarget::default() };
+        assert!(rocket.stages().nth(1).unwrap().vacuum_twr() < 0.5);
+
+        let max = rocket.max_payload(&target).unwrap();
+        let payload = BELL_8081.thrust_at(VACUUM) * 1000.0 / GRAVITY / 0.5 - agena().wet_mass();
+        assert_eq!(Some(Limit::StageTwr(StageNumber { stage: 2, phase: None })), max.limit);
+        assert!(max.payload_mass <= payload && max.payload_mass > payload - 0.1);
+    }
+
+    #[test]
+    fn phases_share_their_stage_number() {
+        let rocket = Rocket { stages: vec![Box::new(boosted()), Box::new(agena())], payload_mass: 0.0 };
+
+        let numbers = rocket.stage_numbers().iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!["1a", "1b", "2"], numbers);
+        assert_eq!(rocket.stages().count(), numbers.len());
+    }
+}
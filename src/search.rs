@@ -0,0 +1,451 @@
+//! Searching the catalog for engine combinations.
+//!
+//! Every stage is built from one engine type from the catalog, up to
+//! `max_engine_count` of them, optionally with a pair of verniers (engines
+//! flagged as `vernier` in the catalog), and the bottom stage can have
+//! boosters with a single engine each. Stages carry enough propellant for
+//! their engines' rated burn time, in tanks following `tanks`.
+//!
+//! Designs are built from the top down, and a partial stack is abandoned as
+//! soon as one of its stages breaks a TWR or g-force limit, or it can no
+//! longer beat the designs already found.
+
+use std::f64;
+
+use catalog::Catalog;
+use design::{BoosterDesign, EngineRef, RocketDesign, StageDesign};
+use engines::Engine;
+use rocket::{PayloadTarget, Rocket};
+use stage::{BoostedStage, BoosterGroup, SimpleStage, Stage};
+use tanks::TankModel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ranking {
+    /// Lightest first
+    LiftoffMass,
+    /// Cheapest engines first, then lightest. Engines with no known cost
+    /// count as free.
+    Cost,
+    /// Largest share of the lift-off mass that can be payload at the
+    /// target delta-v first. The fraction uses the max payload rather than
+    /// the search payload, which only counts towards the TWR and g-force
+    /// limits. Partial stacks can't be abandoned for this, and complete ones
+    /// which might make the results have their max payload found, so it's
+    /// much slower than the other rankings.
+    PayloadFraction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchConfig {
+    pub payload_mass: f64,
+    pub stage_count: usize,
+    pub max_engine_count: usize,
+    /// Try every vernier in the catalog on every stage
+    pub verniers: bool,
+    /// Numbers of boosters to try on the bottom stage, besides none
+    pub booster_counts: Vec<usize>,
+    pub tanks: TankModel,
+    pub target: PayloadTarget,
+    pub max_g_force: f64,
+    pub ranking: Ranking,
+    /// How many designs to return
+    pub results: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            payload_mass: 0.0,
+            stage_count: 2,
+            max_engine_count: 4,
+            verniers: false,
+            booster_counts: Vec::new(),
+            tanks: TankModel::default(),
+            target: PayloadTarget { min_liftoff_twr: 1.0, ..PayloadTarget::default() },
+            max_g_force: f64::INFINITY,
+            ranking: Ranking::LiftoffMass,
+            results: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub design: RocketDesign,
+    pub liftoff_mass: f64,
+    pub delta_v: f64,
+    pub cost: f64,
+    /// Max payload at the target delta-v over the lift-off mass with it
+    pub payload_fraction: f64,
+}
+
+impl SearchResult {
+    fn score(&self, ranking: Ranking) -> f64 {
+        match ranking {
+            Ranking::LiftoffMass => self.liftoff_mass,
+            Ranking::Cost => self.cost,
+            Ranking::PayloadFraction => -self.payload_fraction,
+        }
+    }
+
+    /// Whether this ranks below `other`. Ties go to the lighter design.
+    fn worse_than(&self, other: &SearchResult, ranking: Ranking) -> bool {
+        let (score, other_score) = (self.score(ranking), other.score(ranking));
+        score > other_score || (score == other_score && self.liftoff_mass > other.liftoff_mass)
+    }
+}
+
+/// The best designs meeting `config`, best first
+pub fn search(catalog: &Catalog, config: &SearchConfig) -> Vec<SearchResult> {
+    let options = StageOptions::new(catalog, config);
+    let mut search = Search { config, options: &options, results: Vec::new() };
+    if config.stage_count > 0 {
+        search.extend(&mut Vec::new());
+    }
+    search.results
+}
+
+/// One way of building a stage
+#[derive(Clone)]
+struct StageOption {
+    design: StageDesign,
+    core: SimpleStage,
+    boosters: Option<(SimpleStage, usize)>,
+}
+
+impl StageOption {
+    fn stage(&self) -> Box<dyn Stage> {
+        match self.boosters {
+            Some((ref booster, count)) => Box::new(BoostedStage {
+                core: self.core.clone(),
+                boosters: vec![BoosterGroup { booster: booster.clone(), count, ignition_time: 0.0 }],
+            }),
+            None => Box::new(self.core.clone()),
+        }
+    }
+}
+
+struct StageOptions {
+    upper: Vec<StageOption>,
+    bottom: Vec<StageOption>,
+}
+
+impl StageOptions {
+    fn new(catalog: &Catalog, config: &SearchConfig) -> Self {
+        let tanks = &config.tanks;
+        let mut verniers = vec![None];
+        if config.verniers {
+            verniers.extend(catalog.engines().iter().filter(|e| e.vernier).map(Some));
+        }
+
+        let mut upper = Vec::new();
+        for engine in catalog.engines().iter().filter(|e| !e.vernier) {
+            for count in 1..config.max_engine_count + 1 {
+                for vernier in &verniers {
+                    let mut core = sized_stage(vec![engine.clone(); count], tanks);
+                    if let Some(vernier) = *vernier {
+                        core = core.with_verniers(vernier.clone());
+                        core.dry_mass = stage_dry_mass(&core.engines, tanks);
+                    }
+                    upper.push(StageOption {
+                        design: StageDesign {
+                            dry_mass: structure_mass(&core, tanks),
+                            engines: vec![engine_ref(engine, count)],
+                            verniers: vernier.map(|v| v.name.to_string()),
+                            boosters: Vec::new(),
+                            crossfeed: false,
+                            booster_section: None,
+                            tank: tank_name(tanks),
+                            coast_days: 0.0,
+                            insulation: None,
+                            ullage_motors: 0,
+                            rcs: false,
+                            jettison: Vec::new(),
+                        },
+                        core,
+                        boosters: None,
+                    });
+                }
+            }
+        }
+
+        let mut bottom = upper.clone();
+        for option in &upper {
+            for &booster_count in config.booster_counts.iter().filter(|&&c| c > 0) {
+                for engine in catalog.engines().iter().filter(|e| !e.vernier) {
+                    let booster = sized_stage(vec![engine.clone()], tanks);
+                    let mut design = option.design.clone();
+                    design.boosters = vec![BoosterDesign {
+                        count: booster_count,
+                        dry_mass: structure_mass(&booster, tanks),
+                        engines: vec![engine_ref(engine, 1)],
+                        tank: tank_name(tanks),
+                        ignition_time: 0.0,
+                    }];
+                    bottom.push(StageOption {
+                        design,
+                        core: option.core.clone(),
+                        boosters: Some((booster, booster_count)),
+                    });
+                }
+            }
+        }
+
+        StageOptions { upper, bottom }
+    }
+}
+
+struct Search<'a> {
+    config: &'a SearchConfig,
+    options: &'a StageOptions,
+    results: Vec<SearchResult>,
+}
+
+impl<'a> Search<'a> {
+    /// Adds every option for the next stage down below `stack`, which is
+    /// listed from the top down
+    fn extend(&mut self, stack: &mut Vec<&'a StageOption>) {
+        let is_bottom = stack.len() + 1 == self.config.stage_count;
+        let options = if is_bottom { &self.options.bottom } else { &self.options.upper };
+
+        for option in options {
+            stack.push(option);
+            let rocket = Rocket {
+                stages: stack.iter().rev().map(|o| o.stage()).collect(),
+                payload_mass: self.config.payload_mass,
+            };
+            if !self.beaten(&rocket) && self.within_limits(&rocket, is_bottom) {
+                if is_bottom {
+                    self.consider(&rocket, stack);
+                } else {
+                    self.extend(stack);
+                }
+            }
+            stack.pop();
+        }
+    }
+
+    /// Checks every stage against the TWR and g-force limits. The bottom
+    /// stage of a partial stack is an upper stage of the finished rocket.
+    fn within_limits(&self, rocket: &Rocket, complete: bool) -> bool {
+        rocket.twr_limit(&self.config.target, complete).is_none() &&
+            rocket.stages().all(|stage| stage.max_g_force() <= self.config.max_g_force)
+    }
+
+    /// Whether adding stages below `rocket` can't get it into the results.
+    /// Lift-off mass and cost only go up as stages are added.
+    fn beaten(&self, rocket: &Rocket) -> bool {
+        let worst = match self.results.last() {
+            Some(worst) if self.results.len() >= self.config.results => worst,
+            _ => return false,
+        };
+        let mass = liftoff_mass(rocket);
+        match self.config.ranking {
+            Ranking::LiftoffMass => mass > worst.liftoff_mass,
+            Ranking::Cost => {
+                let cost = cost(rocket);
+                cost > worst.cost || (cost == worst.cost && mass > worst.liftoff_mass)
+            }
+            Ranking::PayloadFraction => false,
+        }
+    }
+
+    fn consider(&mut self, rocket: &Rocket, stack: &[&StageOption]) {
+        let ranking = self.config.ranking;
+        let delta_v = rocket.delta_v();
+        if ranking != Ranking::PayloadFraction && delta_v < self.config.target.delta_v {
+            return;
+        }
+
+        if ranking == Ranking::PayloadFraction && !self.might_beat_payload_fraction(rocket, stack) {
+            return;
+        }
+
+        let max_payload = rocket.max_payload(&self.config.target).map(|m| m.payload_mass);
+        let payload_fraction = match max_payload {
+            Some(max) => max / (liftoff_mass(rocket) - rocket.payload_mass + max),
+            None if ranking == Ranking::PayloadFraction => return,
+            None => 0.0,
+        };
+        let result = SearchResult {
+            design: RocketDesign {
+                payload_mass: self.config.payload_mass,
+                stages: stack.iter().rev().map(|o| o.design.clone()).collect(),
+                fairing: None,
+                escape_system: None,
+            },
+            liftoff_mass: liftoff_mass(rocket),
+            delta_v,
+            cost: cost(rocket),
+            payload_fraction,
+        };
+        let position = self.results.iter()
+            .position(|r| r.worse_than(&result, ranking))
+            .unwrap_or(self.results.len());
+        self.results.insert(position, result);
+        self.results.truncate(self.config.results);
+    }
+
+    /// Whether the rocket still reaches the target delta-v with the payload
+    /// which would give it the same payload fraction as the worst result.
+    /// If not, its max payload is smaller than that.
+    fn might_beat_payload_fraction(&self, rocket: &Rocket, stack: &[&StageOption]) -> bool {
+        let worst = match self.results.last() {
+            Some(worst) if self.results.len() >= self.config.results => worst.payload_fraction,
+            _ => return true,
+        };
+        let empty_mass = liftoff_mass(rocket) - rocket.payload_mass;
+        let rocket = Rocket {
+            stages: stack.iter().rev().map(|o| o.stage()).collect(),
+            payload_mass: worst * empty_mass / (1.0 - worst),
+        };
+        rocket.delta_v() >= self.config.target.delta_v
+    }
+}
+
+fn liftoff_mass(rocket: &Rocket) -> f64 {
+    rocket.stages.iter().map(|s| s.wet_mass()).sum::<f64>() + rocket.payload_mass
+}
+
+fn cost(rocket: &Rocket) -> f64 {
+    rocket.stages.iter().flat_map(|s| s.all_engines()).map(|e| e.cost).sum()
+}
+
+fn engine_ref(engine: &Engine, count: usize) -> EngineRef {
+    EngineRef { name: engine.name.to_string(), count, burn_time: None }
+}
+
+/// The tank type to put in designs, so they build the same stages
+fn tank_name(tanks: &TankModel) -> Option<String> {
+    match *tanks {
+        TankModel::Type(tank_type) => Some(tank_type.name.into()),
+        TankModel::MassFraction(_) => None,
+    }
+}
+
+/// Dry mass to put in designs. Designs with a tank type add the engines and
+/// tanks themselves.
+fn structure_mass(stage: &SimpleStage, tanks: &TankModel) -> f64 {
+    match *tanks {
+        TankModel::Type(_) => 0.0,
+        TankModel::MassFraction(_) => stage.dry_mass,
+    }
+}
+
+fn sized_stage(engines: Vec<Engine>, tanks: &TankModel) -> SimpleStage {
+    SimpleStage { dry_mass: stage_dry_mass(&engines, tanks), engines }
+}
+
+/// Engines plus tanks for their full burn
+fn stage_dry_mass(engines: &[Engine], tanks: &TankModel) -> f64 {
+    engines.iter().map(|e| e.mass).sum::<f64>() + tanks.mass_for(engines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engines::{AJ10_42, BELL_8081, H1, LR101_NA_11, LR105_NA_5};
+
+    fn catalog(engines: Vec<Engine>) -> Catalog {
+        let mut catalog = Catalog::default();
+        for engine in engines {
+            catalog.insert_engine(engine);
+        }
+        catalog
+    }
+
+    fn config(ranking: Ranking, results: usize) -> SearchConfig {
+        SearchConfig {
+            payload_mass: 500.0,
+            stage_count: 1,
+            max_engine_count: 2,
+            target: PayloadTarget { delta_v: 3000.0, ..PayloadTarget::default() },
+            ranking,
+            results,
+            ..SearchConfig::default()
+        }
+    }
+
+    fn masses(results: &[SearchResult]) -> Vec<f64> {
+        results.iter().map(|r| r.liftoff_mass).collect()
+    }
+
+    #[test]
+    fn lightest_design_comes_first() {
+        let catalog = catalog(vec![BELL_8081]);
+        let results = search(&catalog, &config(Ranking::LiftoffMass, 10));
+
+        // One engine and a tenth of its propellant in tanks, under the payload
+        let propellant = BELL_8081.propellant_mass_for_full_burn();
+        assert_eq!(2, results.len());
+        assert!((results[0].liftoff_mass - (500.0 + 132.0 + 1.1 * propellant)).abs() < 1e-6);
+        assert!((results[1].liftoff_mass - (500.0 + 2.0 * (132.0 + 1.1 * propellant))).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cheapest_design_comes_first() {
+        let catalog = catalog(vec![
+            Engine { cost: 2000.0, ..BELL_8081 },
+            Engine { cost: 500.0, ..AJ10_42 },
+            Engine { cost: 5000.0, ..LR105_NA_5 },
+        ]);
+        let results = search(&catalog, &config(Ranking::Cost, 3));
+        let costs = results.iter().map(|r| r.cost).collect::<Vec<_>>();
+        assert_eq!(vec![500.0, 1000.0, 2000.0], costs);
+    }
+
+    #[test]
+    fn unknown_costs_rank_by_mass() {
+        let catalog = catalog(vec![BELL_8081, AJ10_42, LR105_NA_5]);
+        let by_cost = search(&catalog, &config(Ranking::Cost, 3));
+        let by_mass = search(&catalog, &config(Ranking::LiftoffMass, 3));
+        assert_eq!(3, by_cost.len());
+        assert_eq!(masses(&by_mass), masses(&by_cost));
+    }
+
+    #[test]
+    fn pruning_keeps_the_best_designs() {
+        let catalog = catalog(vec![BELL_8081, AJ10_42, LR105_NA_5]);
+        for &ranking in &[Ranking::LiftoffMass, Ranking::Cost, Ranking::PayloadFraction] {
+            let config = SearchConfig { stage_count: 2, ..config(ranking, 3) };
+            let all = search(&catalog, &SearchConfig { results: 1000, ..config.clone() });
+            let best = search(&catalog, &config);
+            assert_eq!(masses(&all[..3]), masses(&best));
+        }
+    }
+
+    #[test]
+    fn verniers_are_flagged_in_the_catalog() {
+        let catalog = catalog(vec![
+            BELL_8081,
+            Engine { name: "Roll thruster".into(), ..LR101_NA_11 },
+            Engine { name: "Vernier-less AJ10".into(), vernier: false, ..AJ10_42 },
+        ]);
+        let config = SearchConfig { verniers: true, ..config(Ranking::LiftoffMass, 1000) };
+        let results = search(&catalog, &config);
+
+        let stages = results.iter().map(|r| &r.design.stages[0]).collect::<Vec<_>>();
+        assert!(stages.iter().any(|s| s.verniers.as_ref().map(|v| &v[..]) == Some("Roll thruster")));
+        assert!(stages.iter().any(|s| s.engines[0].name == "Vernier-less AJ10"));
+        assert!(stages.iter().all(|s| s.engines[0].name != "Roll thruster"));
+    }
+
+    #[test]
+    fn boosted_stages_need_the_stage_twr_at_ignition_only() {
+        let catalog = catalog(vec![BELL_8081, H1]);
+        let config = SearchConfig {
+            stage_count: 2,
+            booster_counts: vec![2],
+            target: PayloadTarget { delta_v: 5000.0, min_liftoff_twr: 1.0, min_stage_twr: 0.5, ..PayloadTarget::default() },
+            ..config(Ranking::LiftoffMass, 1000)
+        };
+        let results = search(&catalog, &config);
+
+        // The Bell core is too weak to carry the upper stage alone once the
+        // boosters drop, which doesn't matter
+        let sustained = results.iter()
+            .map(|r| r.design.build(&catalog).unwrap().with_payload_mass(500.0))
+            .find(|rocket| rocket.stages().nth(1).unwrap().vacuum_twr() < 0.5);
+        assert!(sustained.is_some());
+    }
+}
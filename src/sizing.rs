@@ -0,0 +1,246 @@
+//! Choosing how much propellant each stage carries.
+//!
+//! Each stage keeps its engines and the part of its dry mass that isn't tank,
+//! and gets tanks whose mass is a fixed fraction of the propellant they hold,
+//! as given by a `TankModel`. Every engine's burn in a stage is scaled by the
+//! same factor, so boosters, booster sections and jettisons keep their
+//! layout (see `Stage::resized`).
+//!
+//! The optimizer splits the target delta-v between stages, finds the factor
+//! giving each stage its share from the top down, and searches for the split
+//! with the lowest lift-off mass.
+
+use std::f64;
+
+use engines::Engine;
+use rocket::{PayloadTarget, Rocket};
+use stage::Stage;
+use tanks::TankModel;
+
+/// Steps the target delta-v is divided into for the initial search
+const SEARCH_STEPS: usize = 20;
+/// Largest factor a stage's burn can be scaled by
+const MAX_FACTOR: f64 = 1e4;
+
+struct SizingStage<'a> {
+    stage: &'a dyn Stage,
+    /// Tank dry mass per kg of propellant
+    tank_fraction: f64,
+}
+
+impl<'a> SizingStage<'a> {
+    fn resized(&self, factor: f64) -> Box<dyn Stage> {
+        self.stage.resized(factor, self.tank_fraction)
+    }
+
+    /// Delta-v of every phase of the stage scaled by `factor`, carrying
+    /// `payload_mass`
+    fn delta_v(&self, factor: f64, payload_mass: f64) -> f64 {
+        Rocket { stages: vec![self.resized(factor)], payload_mass }.delta_v()
+    }
+}
+
+/// Resizes the stages of `rocket` so that it reaches `target.delta_v` with
+/// its payload, meeting the TWR minimums of `target`, with the lowest
+/// lift-off mass. The tanks already in each stage are assumed to follow
+/// `tanks`, and are removed before resizing, but never below the rest of the
+/// stage's dry mass. `None` if no split of the delta-v works, or a stage
+/// carries no propellant to scale.
+pub fn size_stages(rocket: &Rocket, tanks: &TankModel, target: &PayloadTarget) -> Option<Rocket> {
+    let stages = rocket.stages.iter()
+        .map(|s| {
+            let engines = s.all_engines();
+            let tank_fraction = tanks.mass_fraction(&engines);
+            let propellant = engines.iter().map(Engine::propellant_mass_for_full_burn).sum::<f64>();
+            if propellant <= 0.0 {
+                return None;
+            }
+            Some(SizingStage { stage: &**s, tank_fraction })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if stages.is_empty() {
+        return None;
+    }
+
+    let sizer = Sizer { stages: &stages, target, payload_mass: rocket.payload_mass };
+    let mut best = None;
+    let step = target.delta_v / SEARCH_STEPS as f64;
+    sizer.search(&mut vec![0; stages.len()], 0, SEARCH_STEPS, step, &mut best);
+    let (mut split, mut mass) = best?;
+
+    // Move delta-v between pairs of stages while it helps, with ever
+    // smaller steps
+    let mut step = step / 2.0;
+    while step > 1.0 {
+        let mut improved = false;
+        for from in 0..split.len() {
+            for to in 0..split.len() {
+                if from == to || split[from] < step {
+                    continue;
+                }
+                let mut candidate = split.clone();
+                candidate[from] -= step;
+                candidate[to] += step;
+                if let Some((m, _)) = sizer.solve(&candidate) {
+                    if m < mass {
+                        split = candidate;
+                        mass = m;
+                        improved = true;
+                    }
+                }
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+
+    let (_, factors) = sizer.solve(&split)?;
+    let stages = stages.iter().zip(factors)
+        .map(|(stage, factor)| stage.resized(factor))
+        .collect();
+    Some(Rocket { stages, payload_mass: rocket.payload_mass })
+}
+
+struct Sizer<'a> {
+    stages: &'a [SizingStage<'a>],
+    target: &'a PayloadTarget,
+    payload_mass: f64,
+}
+
+impl<'a> Sizer<'a> {
+    /// Tries every way of dividing `remaining` steps between the stages from
+    /// `index` on
+    fn search(&self, steps: &mut Vec<usize>, index: usize, remaining: usize, step: f64, best: &mut Option<(Vec<f64>, f64)>) {
+        if index == steps.len() - 1 {
+            steps[index] = remaining;
+            let split = steps.iter().map(|&s| s as f64 * step).collect::<Vec<_>>();
+            if let Some((mass, _)) = self.solve(&split) {
+                if best.as_ref().map(|b| mass < b.1).unwrap_or(true) {
+                    *best = Some((split, mass));
+                }
+            }
+            return;
+        }
+        for n in 0..remaining + 1 {
+            steps[index] = n;
+            self.search(steps, index + 1, remaining - n, step, best);
+        }
+    }
+
+    /// Lift-off mass and the factor each stage's burn is scaled by when each
+    /// stage provides the given delta-v. `None` if a stage can't provide it
+    /// or would be too heavy for its engines.
+    fn solve(&self, split: &[f64]) -> Option<(f64, Vec<f64>)> {
+        let mut mass_above = self.payload_mass;
+        let mut factors = vec![0.0; split.len()];
+
+        for (i, stage) in self.stages.iter().enumerate().rev() {
+            factors[i] = factor_for(stage, split[i], mass_above)?;
+
+            let rocket = Rocket { stages: vec![stage.resized(factors[i])], payload_mass: mass_above };
+            let ignition = rocket.stages().next()?;
+            let (twr, min_twr) = if i == 0 {
+                (ignition.twr(), self.target.min_liftoff_twr)
+            } else {
+                (ignition.vacuum_twr(), self.target.min_stage_twr)
+            };
+            if twr < min_twr {
+                return None;
+            }
+            mass_above += rocket.stages[0].wet_mass();
+        }
+
+        Some((mass_above, factors))
+    }
+}
+
+/// The factor to scale `stage` by so it gives `delta_v` carrying
+/// `payload_mass`, found by bisection. Delta-v only grows with the factor, up
+/// to a limit set by the tanks. `None` if the limit is below `delta_v`.
+fn factor_for(stage: &SizingStage, delta_v: f64, payload_mass: f64) -> Option<f64> {
+    if delta_v <= 0.0 {
+        return Some(0.0);
+    }
+    let mut low = 0.0;
+    let mut high = 1.0;
+    while stage.delta_v(high, payload_mass) < delta_v {
+        if high > MAX_FACTOR {
+            return None;
+        }
+        low = high;
+        high *= 2.0;
+    }
+    while high - low > high * 1e-9 {
+        let middle = (low + high) / 2.0;
+        if stage.delta_v(middle, payload_mass) >= delta_v {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+    Some(high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engines::{BELL_8081, CASTOR_1, LR105_NA_5};
+    use stage::{BoostedStage, BoosterGroup, SimpleStage, GRAVITY};
+
+    /// A stage of `engines` with `structure` kg besides tanks of a tenth of
+    /// its propellant
+    fn stage(structure: f64, engines: Vec<Engine>) -> SimpleStage {
+        let mut stage = SimpleStage { dry_mass: 0.0, engines };
+        stage.dry_mass = structure + 0.1 * stage.wet_mass();
+        stage
+    }
+
+    fn liftoff_mass(rocket: &Rocket) -> f64 {
+        rocket.stages().next().unwrap().wet_mass()
+    }
+
+    #[test]
+    fn single_stage_matches_the_rocket_equation() {
+        let rocket = Rocket { stages: vec![Box::new(stage(500.0, vec![BELL_8081]))], payload_mass: 1000.0 };
+        let target = PayloadTarget { delta_v: 3000.0, ..PayloadTarget::default() };
+        let sized = size_stages(&rocket, &TankModel::MassFraction(0.1), &target).unwrap();
+
+        // (500 + 1000 + 0.1p + p) / (500 + 1000 + 0.1p) = R
+        let mass_ratio = (3000.0 / (BELL_8081.isp * GRAVITY)).exp();
+        let propellant = 1500.0 * (mass_ratio - 1.0) / (1.1 - 0.1 * mass_ratio);
+        assert!((liftoff_mass(&sized) - (1500.0 + 1.1 * propellant)).abs() < 0.1);
+        assert!((sized.delta_v() - 3000.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn boosted_stage_keeps_its_boosters() {
+        let boosted = BoostedStage {
+            core: stage(1500.0, vec![LR105_NA_5]),
+            boosters: vec![BoosterGroup { booster: stage(200.0, vec![CASTOR_1]), count: 2, ignition_time: 10.0 }],
+        };
+        let rocket = Rocket {
+            stages: vec![Box::new(boosted), Box::new(stage(300.0, vec![BELL_8081]))],
+            payload_mass: 500.0,
+        };
+        let lumped = Rocket {
+            stages: vec![
+                Box::new(stage(1900.0, vec![LR105_NA_5, CASTOR_1, CASTOR_1])),
+                Box::new(stage(300.0, vec![BELL_8081])),
+            ],
+            payload_mass: 500.0,
+        };
+        let target = PayloadTarget { delta_v: 9000.0, ..PayloadTarget::default() };
+        let tanks = TankModel::MassFraction(0.1);
+        let sized = size_stages(&rocket, &tanks, &target).unwrap();
+        let sized_lumped = size_stages(&lumped, &tanks, &target).unwrap();
+
+        assert!((sized.delta_v() - 9000.0).abs() < 0.1);
+        // The core lights alone, the boosters join it, and burn out before it
+        let phases = sized.stages().map(|s| s.engines().len()).collect::<Vec<_>>();
+        assert_eq!(vec![1, 3, 1, 1], phases);
+        // Dropping the boosters helps, rather than the core's propellant
+        // counting as structure
+        assert!(liftoff_mass(&sized) < liftoff_mass(&sized_lumped));
+    }
+}
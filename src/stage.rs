@@ -0,0 +1,891 @@
+use std::collections::HashMap;
+use std::f64;
+
+use engines::{Engine, SEA_LEVEL, THRUSTER_2, VACUUM};
+use error::Error;
+use tanks::Insulation;
+
+/// Standard gravity, used to convert Isp to exhaust velocity and thrust to g.
+pub const GRAVITY: f64 = 9.82;
+/// Mass of the Atlas booster section left behind at staging, minus engines.
+pub const ATLAS_DECOUPLER_MASS: f64 = 1610.0;
+
+/// Anything that burns propellant to produce delta-v. Masses are in kg,
+/// times in seconds.
+pub trait Stage {
+    fn engines(&self) -> Vec<Engine>;
+    fn dry_mass(&self) -> f64;
+    fn wet_mass(&self) -> f64;
+
+    /// Every engine belonging to the stage, including those which only
+    /// run in later phases of its burn.
+    fn all_engines(&self) -> Vec<Engine> {
+        self.engines()
+    }
+
+    fn burn_time(&self) -> f64 {
+        self.engines().iter().map(|e| e.burn_time)
+            .max_by_key(|x| *x as u64).unwrap_or(0.0)
+    }
+
+    fn isp(&self) -> f64 {
+        self.isp_at(VACUUM)
+    }
+
+    /// Combined Isp of all engines at the given ambient pressure, in
+    /// atmospheres.
+    fn isp_at(&self, pressure: f64) -> f64 {
+        let engines = self.engines();
+        engines.iter().map(|e| e.thrust_at(pressure)).sum::<f64>() /
+            engines.iter().map(|e| e.thrust_at(pressure) / e.isp_at(pressure)).sum::<f64>()
+    }
+
+    fn thrust_at(&self, pressure: f64) -> f64 {
+        self.engines().iter().map(|e| e.thrust_at(pressure)).sum()
+    }
+
+    /// Simple stages don't need to implement this method. It is used to
+    /// calculate delta-v when there are boosters involved. To combine multiple
+    /// simple stages, use `Rocket` instead.
+    fn next_stage(&self) -> Option<Box<dyn Stage>> {
+        None
+    }
+
+    /// A copy of the stage with every engine's burn, and the propellant for
+    /// it, scaled by `factor`. The dry mass gains `tank_fraction` kg of tanks
+    /// for each kg of propellant added. Stages made of several parts keep
+    /// their layout, anything else becomes a `SimpleStage` of `all_engines`.
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> {
+        let engines = self.all_engines();
+        let propellant = engines.iter().map(Engine::propellant_mass_for_full_burn).sum::<f64>();
+        let stage = SimpleStage { dry_mass: self.wet_mass() - propellant, engines };
+        Box::new(stage.scaled(factor, tank_fraction))
+    }
+
+    /// Delta-v in a vacuum.
+    fn delta_v(&self) -> f64 {
+        self.delta_v_at(VACUUM)
+    }
+
+    /// Delta-v if the whole burn happened at the given ambient pressure.
+    /// Use `SEA_LEVEL` for a pessimistic figure for first stages.
+    fn delta_v_at(&self, pressure: f64) -> f64 {
+        self.isp_at(pressure) * (self.wet_mass() / self.dry_mass()).ln() * GRAVITY
+    }
+
+    fn max_g_force(&self) -> f64 {
+        self.thrust_at(VACUUM) * 1000.0 / self.dry_mass() / GRAVITY
+    }
+
+    /// Chance that every engine lights and burns to completion.
+    fn reliability(&self) -> f64 {
+        self.all_engines().iter().map(Engine::reliability).product()
+    }
+
+    /// The burn in a vacuum when the engines throttle down to stay under
+    /// `max_g_force`. Runs at full throttle until the limit is reached, then
+    /// holds the limit until the engines hit their minimum throttle.
+    fn g_limited_burn(&self, max_g_force: f64) -> GLimitedBurn {
+        let engines = self.engines();
+        let max_thrust = engines.iter().map(|e| e.thrust * e.max_throttle).sum::<f64>() * 1000.0;
+        let min_thrust = engines.iter().map(|e| e.thrust * e.min_throttle).sum::<f64>() * 1000.0;
+        let exhaust_velocity = self.isp() * GRAVITY;
+        let limit = max_g_force * GRAVITY;
+        let (wet_mass, dry_mass) = (self.wet_mass(), self.dry_mass());
+
+        let throttle_mass = (max_thrust / limit).max(dry_mass).min(wet_mass);
+        let min_throttle_mass = (min_thrust / limit).max(dry_mass).min(throttle_mass);
+        let mut burn_time = (wet_mass - throttle_mass) * exhaust_velocity / max_thrust;
+        burn_time += exhaust_velocity / limit * (throttle_mass / min_throttle_mass).ln();
+        if min_throttle_mass > dry_mass {
+            burn_time += (min_throttle_mass - dry_mass) * exhaust_velocity / min_thrust;
+        }
+
+        let end_thrust = if min_throttle_mass > dry_mass {
+            min_thrust
+        } else {
+            max_thrust.min(limit * dry_mass)
+        };
+        let peak_g_force = end_thrust / dry_mass / GRAVITY;
+        GLimitedBurn {
+            burn_time,
+            max_g_force: peak_g_force,
+            achievable: peak_g_force <= max_g_force * (1.0 + 1e-9),
+        }
+    }
+
+    /// TWR at lift-off, using sea level thrust.
+    fn twr(&self) -> f64 {
+        self.twr_at(SEA_LEVEL)
+    }
+
+    /// TWR at ignition, for stages lit above the atmosphere.
+    fn vacuum_twr(&self) -> f64 {
+        self.twr_at(VACUUM)
+    }
+
+    fn twr_at(&self, pressure: f64) -> f64 {
+        self.thrust_at(pressure) * 1000.0 / self.wet_mass() / GRAVITY
+    }
+
+    fn propellants_required(&self) -> HashMap<String, f64> {
+        let mut result = HashMap::new();
+        for engine in self.engines() {
+            for (prop, amount) in engine.propellants_required() {
+                *result.entry(prop.name.into_owned()).or_insert(0.0) += amount;
+            }
+        }
+        result
+    }
+}
+
+impl<T: ?Sized + Stage> Stage for Box<T> {
+    fn engines(&self) -> Vec<Engine> { (**self).engines() }
+    fn all_engines(&self) -> Vec<Engine> { (**self).all_engines() }
+    fn dry_mass(&self) -> f64 { (**self).dry_mass() }
+    fn wet_mass(&self) -> f64 { (**self).wet_mass() }
+    fn burn_time(&self) -> f64 { (**self).burn_time() }
+    fn next_stage(&self) -> Option<Box<dyn Stage>> { (**self).next_stage() }
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> { (**self).resized(factor, tank_fraction) }
+    fn delta_v(&self) -> f64 { (**self).delta_v() }
+    fn delta_v_at(&self, pressure: f64) -> f64 { (**self).delta_v_at(pressure) }
+}
+
+impl<T: ?Sized + Stage> Stage for &T {
+    fn engines(&self) -> Vec<Engine> { (**self).engines() }
+    fn all_engines(&self) -> Vec<Engine> { (**self).all_engines() }
+    fn dry_mass(&self) -> f64 { (**self).dry_mass() }
+    fn wet_mass(&self) -> f64 { (**self).wet_mass() }
+    fn burn_time(&self) -> f64 { (**self).burn_time() }
+    fn next_stage(&self) -> Option<Box<dyn Stage>> { (**self).next_stage() }
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> { (**self).resized(factor, tank_fraction) }
+    fn delta_v(&self) -> f64 { (**self).delta_v() }
+    fn delta_v_at(&self, pressure: f64) -> f64 { (**self).delta_v_at(pressure) }
+}
+
+/// Result of `Stage::g_limited_burn`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct GLimitedBurn {
+    pub burn_time: f64,
+    /// Highest g-force during the burn, which is over the limit if the
+    /// engines can't throttle down far enough
+    pub max_g_force: f64,
+    pub achievable: bool,
+}
+
+/// A single set of tanks feeding engines which all burn for the same time.
+#[derive(Debug, Clone)]
+pub struct SimpleStage {
+    pub dry_mass: f64,
+    pub engines: Vec<Engine>,
+}
+
+impl SimpleStage {
+    pub fn with_remaining_burn_time(&self, burn_time: f64) -> Self {
+        let mut new_stage = self.clone();
+        for engine in &mut new_stage.engines {
+            engine.burn_time = burn_time;
+        }
+        new_stage
+    }
+
+    pub fn with_verniers(mut self, vernier: Engine) -> Self {
+        let vernier = vernier.with_burn_time(self.engines[0].burn_time);
+        self.engines.push(vernier.clone());
+        self.engines.push(vernier);
+        self
+    }
+
+    /// The stage with every engine's burn scaled by `factor`, and
+    /// `tank_fraction` kg of tanks added for each kg of propellant added.
+    /// Removing more tanks than the stage has leaves it with no dry mass.
+    pub fn scaled(&self, factor: f64, tank_fraction: f64) -> Self {
+        let propellant = self.wet_mass() - self.dry_mass;
+        SimpleStage {
+            dry_mass: (self.dry_mass + tank_fraction * propellant * (factor - 1.0)).max(0.0),
+            engines: self.engines.iter().map(|e| e.with_burn_time(e.burn_time * factor)).collect(),
+        }
+    }
+
+    /// The stage after `days` of boiloff before ignition. Each engine loses
+    /// burn time to whichever of its propellants runs short first, and the
+    /// rest of the others stays in the tanks as dead weight.
+    pub fn after_boiloff(&self, days: f64, insulation: &Insulation) -> Self {
+        let mut new_stage = self.clone();
+        for engine in &mut new_stage.engines {
+            let remaining = engine.fuel_consumption.iter()
+                .map(|&(ref fuel, _rate)| (1.0 - fuel.boiloff_rate * insulation.boiloff_factor).max(0.0).powf(days))
+                .collect::<Vec<_>>();
+            let usable = remaining.iter().cloned().fold(1.0, f64::min);
+            new_stage.dry_mass += engine.fuel_consumption.iter().zip(&remaining)
+                .map(|(&(ref fuel, rate), &left)| rate * engine.burn_time * (left - usable) * fuel.density)
+                .sum::<f64>();
+            engine.burn_time *= usable;
+        }
+        new_stage
+    }
+}
+
+impl Stage for SimpleStage {
+    fn engines(&self) -> Vec<Engine> { self.engines.clone() }
+    fn dry_mass(&self) -> f64 { self.dry_mass }
+
+    fn wet_mass(&self) -> f64 {
+        self.dry_mass + self.engines.iter().map(|e| e.propellant_mass_for_full_burn()).sum::<f64>()
+    }
+
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> {
+        Box::new(self.scaled(factor, tank_fraction))
+    }
+}
+
+/// A core stage with groups of boosters strapped on. Each group can light
+/// after the core and burn for longer or shorter than it, and is jettisoned
+/// once all of its engines burn out. The core is only dropped with the
+/// whole stage.
+///
+/// The burn is split into phases wherever an engine lights or burns out.
+/// As a `Stage`, this is the first phase, and `next_stage` yields the rest.
+#[derive(Debug, Clone)]
+pub struct BoostedStage {
+    pub core: SimpleStage,
+    pub boosters: Vec<BoosterGroup>,
+}
+
+/// `count` copies of `booster`, all lit together.
+#[derive(Debug, Clone)]
+pub struct BoosterGroup {
+    pub booster: SimpleStage,
+    pub count: usize,
+    /// Seconds after the core lights
+    pub ignition_time: f64,
+}
+
+impl BoostedStage {
+    /// Each part of the burn during which the same engines are running,
+    /// carrying what's still attached. Gaps with nothing running are left
+    /// out.
+    pub fn phases(&self) -> Vec<SimpleStage> {
+        let parts = Some((&self.core, 1, 0.0)).into_iter()
+            .chain(self.boosters.iter().map(|g| (&g.booster, g.count, g.ignition_time)))
+            .collect::<Vec<_>>();
+        let mut times = parts.iter()
+            .flat_map(|&(stage, _, ignition)| {
+                stage.engines.iter().map(move |e| ignition + e.burn_time).chain(Some(ignition))
+            })
+            .collect::<Vec<_>>();
+        times.sort_by(|a, b| a.partial_cmp(b).expect("We should never get NaN here"));
+        times.dedup();
+
+        let mut attached = vec![true; parts.len()];
+        let mut mass = self.wet_mass();
+        let mut phases = Vec::new();
+        for window in times.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            let mut engines = Vec::new();
+            for (&(stage, count, ignition), _) in parts.iter().zip(&attached).filter(|&(_, &a)| a) {
+                let running = stage.engines.iter()
+                    .filter(|e| ignition <= start && ignition + e.burn_time >= end)
+                    .map(|e| e.with_burn_time(end - start));
+                for engine in running {
+                    engines.extend(vec![engine; count]);
+                }
+            }
+            if !engines.is_empty() {
+                let phase = SimpleStage { dry_mass: 0.0, engines };
+                mass -= phase.wet_mass();
+                phases.push(SimpleStage { dry_mass: mass, ..phase });
+            }
+
+            // Boosters are dropped as soon as they burn out
+            for (i, &(stage, count, ignition)) in parts.iter().enumerate().skip(1) {
+                if attached[i] && ignition + stage.burn_time() <= end {
+                    attached[i] = false;
+                    mass -= stage.dry_mass * count as f64;
+                }
+            }
+        }
+        phases
+    }
+
+    fn first_phase(&self) -> SimpleStage {
+        self.phases().into_iter().next()
+            .unwrap_or_else(|| SimpleStage { dry_mass: self.wet_mass(), ..self.core.with_remaining_burn_time(0.0) })
+    }
+}
+
+impl Stage for BoostedStage {
+    fn engines(&self) -> Vec<Engine> {
+        self.first_phase().engines
+    }
+
+    fn all_engines(&self) -> Vec<Engine> {
+        let mut engines = self.core.engines();
+        for group in &self.boosters {
+            for _ in 0..group.count {
+                engines.extend(group.booster.engines());
+            }
+        }
+        engines
+    }
+
+    fn burn_time(&self) -> f64 {
+        self.first_phase().burn_time()
+    }
+
+    fn dry_mass(&self) -> f64 {
+        self.first_phase().dry_mass
+    }
+
+    fn wet_mass(&self) -> f64 {
+        self.core.wet_mass() +
+            self.boosters.iter().map(|g| g.booster.wet_mass() * g.count as f64).sum::<f64>()
+    }
+
+    fn next_stage(&self) -> Option<Box<dyn Stage>> {
+        later_phases(self.phases())
+    }
+
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> {
+        Box::new(BoostedStage {
+            core: self.core.scaled(factor, tank_fraction),
+            boosters: scaled_boosters(&self.boosters, factor, tank_fraction),
+        })
+    }
+}
+
+fn scaled_boosters(boosters: &[BoosterGroup], factor: f64, tank_fraction: f64) -> Vec<BoosterGroup> {
+    boosters.iter()
+        .map(|g| BoosterGroup { booster: g.booster.scaled(factor, tank_fraction), ..g.clone() })
+        .collect()
+}
+
+/// A core fed by the tanks of its boosters, as in asparagus and onion
+/// staging. Every engine lights together and draws from the tanks of the
+/// first group of boosters until they run dry, when that group is dropped
+/// and the next group feeds the engines left. The core burns its own
+/// propellant once all the boosters are gone. Each booster's propellant is
+/// what its own engines would burn.
+#[derive(Debug, Clone)]
+pub struct CrossfeedStage {
+    pub core: SimpleStage,
+    /// In the order they're dropped
+    pub boosters: Vec<BoosterGroup>,
+}
+
+impl CrossfeedStage {
+    /// One phase per group of boosters, then the core's own burn.
+    pub fn phases(&self) -> Vec<SimpleStage> {
+        let mut mass = self.wet_mass();
+        let mut engines = self.all_engines();
+        let mut phases = Vec::new();
+
+        for group in &self.boosters {
+            let propellant = group.booster.wet_mass() - group.booster.dry_mass;
+            let mass_flow = engines.iter().map(Engine::propellant_mass_per_second).sum::<f64>();
+            if propellant > 0.0 && mass_flow > 0.0 {
+                let burn_time = propellant * group.count as f64 / mass_flow;
+                mass -= propellant * group.count as f64;
+                phases.push(SimpleStage {
+                    dry_mass: mass,
+                    engines: engines.iter().map(|e| e.with_burn_time(burn_time)).collect(),
+                });
+            }
+            mass -= group.booster.dry_mass * group.count as f64;
+            // `all_engines` lists the first group to go last
+            let remaining = engines.len() - group.booster.engines.len() * group.count;
+            engines.truncate(remaining);
+        }
+
+        phases.push(self.core.clone());
+        phases
+    }
+}
+
+impl Stage for CrossfeedStage {
+    fn engines(&self) -> Vec<Engine> {
+        self.phases().remove(0).engines
+    }
+
+    fn all_engines(&self) -> Vec<Engine> {
+        let mut engines = self.core.engines();
+        for group in self.boosters.iter().rev() {
+            for _ in 0..group.count {
+                engines.extend(group.booster.engines());
+            }
+        }
+        engines
+    }
+
+    fn burn_time(&self) -> f64 {
+        self.phases().remove(0).burn_time()
+    }
+
+    fn dry_mass(&self) -> f64 {
+        self.phases().remove(0).dry_mass
+    }
+
+    fn wet_mass(&self) -> f64 {
+        self.core.wet_mass() +
+            self.boosters.iter().map(|g| g.booster.wet_mass() * g.count as f64).sum::<f64>()
+    }
+
+    fn next_stage(&self) -> Option<Box<dyn Stage>> {
+        later_phases(self.phases())
+    }
+
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> {
+        Box::new(CrossfeedStage {
+            core: self.core.scaled(factor, tank_fraction),
+            boosters: scaled_boosters(&self.boosters, factor, tank_fraction),
+        })
+    }
+}
+
+fn later_phases(mut phases: Vec<SimpleStage>) -> Option<Box<dyn Stage>> {
+    if phases.len() > 1 {
+        phases.remove(0);
+        Some(Box::new(LaterPhases(phases)))
+    } else {
+        None
+    }
+}
+
+/// The phases of a staged burn after the first.
+struct LaterPhases(Vec<SimpleStage>);
+
+impl Stage for LaterPhases {
+    fn engines(&self) -> Vec<Engine> { self.0[0].engines() }
+    fn dry_mass(&self) -> f64 { self.0[0].dry_mass() }
+    fn wet_mass(&self) -> f64 { self.0[0].wet_mass() }
+
+    fn next_stage(&self) -> Option<Box<dyn Stage>> {
+        if self.0.len() > 1 {
+            Some(Box::new(LaterPhases(self.0[1..].to_vec())))
+        } else {
+            None
+        }
+    }
+}
+
+/// A stage-and-a-half, like the Atlas. The booster engines draw from the
+/// sustainer's tanks, and are dropped along with the booster skirt at
+/// `staging_time`, leaving the sustainer to burn the rest of the
+/// propellant. `sustainer` holds the tanks, and its engines' burn times are
+/// counted from lift-off.
+#[derive(Debug, Clone)]
+pub struct SustainerStage {
+    pub sustainer: SimpleStage,
+    pub booster_engines: Vec<Engine>,
+    /// Mass dropped at staging besides the booster engines, usually
+    /// `ATLAS_DECOUPLER_MASS`
+    pub skirt_mass: f64,
+    pub staging_time: f64,
+}
+
+impl SustainerStage {
+    /// The same burn as a core with a tankless booster, which is all the
+    /// phases need to know. The booster's propellant is still in the
+    /// sustainer's tanks, so the tanks' mass stays with the sustainer.
+    pub fn as_boosted_stage(&self) -> BoostedStage {
+        let booster_engines = self.booster_engines.iter()
+            .map(|e| e.with_burn_time(self.staging_time))
+            .collect::<Vec<_>>();
+        let booster = SimpleStage {
+            dry_mass: self.skirt_mass + booster_engines.iter().map(|e| e.mass).sum::<f64>(),
+            engines: booster_engines,
+        };
+        BoostedStage {
+            core: self.sustainer.clone(),
+            boosters: vec![BoosterGroup { booster, count: 1, ignition_time: 0.0 }],
+        }
+    }
+}
+
+impl Stage for SustainerStage {
+    fn engines(&self) -> Vec<Engine> { self.as_boosted_stage().engines() }
+    fn all_engines(&self) -> Vec<Engine> { self.as_boosted_stage().all_engines() }
+    fn dry_mass(&self) -> f64 { self.as_boosted_stage().dry_mass() }
+    fn wet_mass(&self) -> f64 { self.as_boosted_stage().wet_mass() }
+    fn burn_time(&self) -> f64 { self.as_boosted_stage().burn_time() }
+    fn next_stage(&self) -> Option<Box<dyn Stage>> { self.as_boosted_stage().next_stage() }
+
+    /// The booster engines' propellant is in the sustainer's tanks too
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> {
+        let booster_propellant = self.booster_engines.iter()
+            .map(Engine::propellant_mass_for_full_burn)
+            .sum::<f64>();
+        let mut sustainer = self.sustainer.scaled(factor, tank_fraction);
+        sustainer.dry_mass = (sustainer.dry_mass + tank_fraction * booster_propellant * (factor - 1.0)).max(0.0);
+        Box::new(SustainerStage {
+            sustainer,
+            booster_engines: self.booster_engines.iter()
+                .map(|e| e.with_burn_time(e.burn_time * factor))
+                .collect(),
+            staging_time: self.staging_time * factor,
+            ..self.clone()
+        })
+    }
+}
+
+/// When a `Jettison` happens, counted from the stage's ignition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JettisonTrigger {
+    Time(f64),
+    /// Once this fraction of the stage's propellant has burned
+    PropellantFraction(f64),
+}
+
+/// Mass dropped partway through a burn, like an empty drop tank, an
+/// interstage or a fairing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jettison {
+    pub mass: f64,
+    pub trigger: JettisonTrigger,
+}
+
+/// A stage which drops parts of its dry mass during its burn. The burn is
+/// split into phases at each jettison, so `next_stage` yields the rest of
+/// it. Jettisons after the burn ends never happen.
+#[derive(Debug, Clone)]
+pub struct JettisonStage<T> {
+    /// Including the jettisoned masses
+    pub stage: T,
+    pub jettisons: Vec<Jettison>,
+}
+
+impl<T: Stage> JettisonStage<T> {
+    /// Fails unless every jettison happens after ignition, and the stage has
+    /// at least the jettisoned mass left when it burns out
+    pub fn new(stage: T, jettisons: Vec<Jettison>) -> Result<Self, Error> {
+        for jettison in &jettisons {
+            match jettison.trigger {
+                JettisonTrigger::Time(time) if !(time.is_finite() && time > 0.0) => return Err(Error::JettisonTime),
+                JettisonTrigger::PropellantFraction(fraction) if !(fraction > 0.0 && fraction <= 1.0) => {
+                    return Err(Error::JettisonFraction);
+                }
+                _ => (),
+            }
+        }
+        let stage = JettisonStage { stage, jettisons };
+        let burnout_mass = stage.segments().last().map(|s| s.dry_mass).unwrap_or_else(|| stage.stage.dry_mass());
+        let dropped = stage.jettisons.iter().map(|j| j.mass).sum::<f64>();
+        if stage.jettisons.iter().any(|j| !j.mass.is_finite() || j.mass < 0.0) || dropped > burnout_mass {
+            return Err(Error::JettisonMass);
+        }
+        Ok(stage)
+    }
+
+    pub fn phases(&self) -> Vec<SimpleStage> {
+        let segments = self.segments();
+        let mut times = self.jettisons.iter()
+            .map(|j| (self.trigger_time(j.trigger, &segments), j.mass))
+            .collect::<Vec<_>>();
+        times.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("We should never get NaN here"));
+        let mut jettisons = times.into_iter().peekable();
+
+        let mut dropped = 0.0;
+        let mut time = 0.0;
+        let mut phases = Vec::<SimpleStage>::new();
+        for segment in segments {
+            let (start, end) = (time, time + segment.burn_time());
+            let mass_flow = segment.engines.iter().map(Engine::propellant_mass_per_second).sum::<f64>();
+            while let Some((at, mass)) = jettisons.next_if(|&(at, _)| at <= end) {
+                if at > time {
+                    phases.push(SimpleStage {
+                        dry_mass: segment.wet_mass() - mass_flow * (at - start) - dropped,
+                        engines: segment.engines.iter().map(|e| e.with_burn_time(at - time)).collect(),
+                    });
+                    time = at;
+                }
+                dropped += mass;
+            }
+            if end > time {
+                phases.push(SimpleStage {
+                    dry_mass: segment.dry_mass - dropped,
+                    engines: segment.engines.iter().map(|e| e.with_burn_time(end - time)).collect(),
+                });
+                time = end;
+            }
+        }
+        phases
+    }
+
+    /// The phases of the stage, split wherever an engine burns out, so that
+    /// every engine in each segment burns for all of it
+    fn segments(&self) -> Vec<SimpleStage> {
+        let mut phases = vec![SimpleStage { dry_mass: self.stage.dry_mass(), engines: self.stage.engines() }];
+        let mut next = self.stage.next_stage();
+        while let Some(phase) = next {
+            phases.push(SimpleStage { dry_mass: phase.dry_mass(), engines: phase.engines() });
+            next = phase.next_stage();
+        }
+
+        let mut segments = Vec::new();
+        for phase in phases {
+            let mut burnouts = phase.engines.iter().map(|e| e.burn_time).collect::<Vec<_>>();
+            burnouts.sort_by(|a, b| a.partial_cmp(b).expect("We should never get NaN here"));
+            burnouts.dedup();
+            let mut mass = phase.wet_mass();
+            let mut start = 0.0;
+            for burnout in burnouts.into_iter().filter(|&t| t > 0.0) {
+                let engines = phase.engines.iter()
+                    .filter(|e| e.burn_time >= burnout)
+                    .map(|e| e.with_burn_time(burnout - start))
+                    .collect::<Vec<_>>();
+                let segment = SimpleStage { dry_mass: 0.0, engines };
+                mass -= segment.wet_mass();
+                segments.push(SimpleStage { dry_mass: mass, ..segment });
+                start = burnout;
+            }
+        }
+        segments
+    }
+
+    fn trigger_time(&self, trigger: JettisonTrigger, segments: &[SimpleStage]) -> f64 {
+        let fraction = match trigger {
+            JettisonTrigger::Time(time) => return time,
+            JettisonTrigger::PropellantFraction(fraction) => fraction,
+        };
+        let total = segments.iter().map(|s| s.wet_mass() - s.dry_mass).sum::<f64>();
+        let mut left = total * fraction;
+        let mut time = 0.0;
+        for segment in segments {
+            let propellant = segment.wet_mass() - segment.dry_mass;
+            if left <= propellant {
+                let mass_flow = segment.engines.iter().map(Engine::propellant_mass_per_second).sum::<f64>();
+                return time + left / mass_flow;
+            }
+            left -= propellant;
+            time += segment.burn_time();
+        }
+        f64::INFINITY
+    }
+
+    fn first_phase(&self) -> SimpleStage {
+        self.phases().into_iter().next()
+            .unwrap_or_else(|| SimpleStage { dry_mass: self.stage.dry_mass(), engines: self.stage.engines() })
+    }
+}
+
+impl<T: Stage> Stage for JettisonStage<T> {
+    fn engines(&self) -> Vec<Engine> { self.first_phase().engines }
+    fn all_engines(&self) -> Vec<Engine> { self.stage.all_engines() }
+    fn dry_mass(&self) -> f64 { self.first_phase().dry_mass }
+    fn wet_mass(&self) -> f64 { self.first_phase().wet_mass() }
+    fn burn_time(&self) -> f64 { self.first_phase().burn_time() }
+    fn next_stage(&self) -> Option<Box<dyn Stage>> { later_phases(self.phases()) }
+
+    /// Jettisons at a time move with the burn, so they happen at the same
+    /// point in it
+    fn resized(&self, factor: f64, tank_fraction: f64) -> Box<dyn Stage> {
+        let jettisons = self.jettisons.iter()
+            .map(|j| match j.trigger {
+                JettisonTrigger::Time(time) => Jettison { trigger: JettisonTrigger::Time(time * factor), ..*j },
+                JettisonTrigger::PropellantFraction(_) => *j,
+            })
+            .collect();
+        Box::new(JettisonStage {
+            stage: self.stage.resized(factor, tank_fraction),
+            jettisons,
+        })
+    }
+}
+
+/// A stage carrying a fixed mass on top of it, such as the stages above it.
+pub struct StageWithPayload<T> {
+    pub stage: T,
+    pub payload_mass: f64,
+}
+
+impl<T: Stage> Stage for StageWithPayload<T> {
+    fn engines(&self) -> Vec<Engine> {
+        self.stage.engines()
+    }
+
+    fn all_engines(&self) -> Vec<Engine> {
+        self.stage.all_engines()
+    }
+
+    fn dry_mass(&self) -> f64 {
+        self.stage.dry_mass() + self.payload_mass
+    }
+
+    fn wet_mass(&self) -> f64 {
+        self.stage.wet_mass() + self.payload_mass
+    }
+
+    fn next_stage(&self) -> Option<Box<dyn Stage>> {
+        self.stage.next_stage().map(|s| Box::new(StageWithPayload {
+            stage: s,
+            payload_mass: self.payload_mass,
+        }) as Box<dyn Stage>)
+    }
+
+    fn burn_time(&self) -> f64 {
+        self.stage.burn_time()
+    }
+}
+
+/// A stage using a single `THRUSTER_2`, for quickly sketching out probes.
+pub fn probe(dry_mass: f64, burn_time: f64) -> SimpleStage {
+    SimpleStage { dry_mass, engines: vec![THRUSTER_2.with_burn_time(burn_time)] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engines::{BELL_8081, RL10A_1};
+    use fuels::{Fuel, LIQUID_HYDROGEN, LIQUID_OXYGEN};
+    use tanks::{FOAM, MLI, NO_INSULATION};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    /// 1000 kg dry, burning for 100 s
+    fn agena() -> SimpleStage {
+        SimpleStage { dry_mass: 1000.0, engines: vec![BELL_8081.with_burn_time(100.0)] }
+    }
+
+    fn jettison(mass: f64, trigger: JettisonTrigger) -> Jettison {
+        Jettison { mass, trigger }
+    }
+
+    /// Burn time, wet mass and dry mass of each phase
+    fn masses(phases: &[SimpleStage]) -> Vec<(f64, f64, f64)> {
+        phases.iter().map(|p| (p.burn_time(), p.wet_mass(), p.dry_mass)).collect()
+    }
+
+    fn assert_phases(expected: &[(f64, f64, f64)], phases: &[SimpleStage]) {
+        let actual = masses(phases);
+        assert_eq!(expected.len(), actual.len(), "{:?}", actual);
+        for (e, a) in expected.iter().zip(&actual) {
+            assert!(close(e.0, a.0) && close(e.1, a.1) && close(e.2, a.2), "{:?} != {:?}", expected, actual);
+        }
+    }
+
+    #[test]
+    fn jettisons_split_the_burn() {
+        let flow = BELL_8081.propellant_mass_per_second();
+        let stage = JettisonStage::new(agena(), vec![
+            jettison(50.0, JettisonTrigger::PropellantFraction(0.5)),
+            jettison(200.0, JettisonTrigger::Time(20.0)),
+        ]).unwrap();
+
+        // 200 kg go after 20 s, and 50 kg once half of the 100 s of
+        // propellant has burned
+        assert_phases(&[
+            (20.0, 1000.0 + 100.0 * flow, 1000.0 + 80.0 * flow),
+            (30.0, 800.0 + 80.0 * flow, 800.0 + 50.0 * flow),
+            (50.0, 750.0 + 50.0 * flow, 750.0),
+        ], &stage.phases());
+        assert!(close(stage.wet_mass(), agena().wet_mass()));
+        let later = stage.next_stage().unwrap();
+        assert!(close(later.wet_mass(), 800.0 + 80.0 * flow));
+    }
+
+    #[test]
+    fn jettisons_after_burnout_stay_aboard() {
+        let stage = JettisonStage::new(agena(), vec![jettison(200.0, JettisonTrigger::Time(150.0))]).unwrap();
+        assert_phases(&[(100.0, agena().wet_mass(), 1000.0)], &stage.phases());
+    }
+
+    #[test]
+    fn jettisons_split_segments_where_engines_burn_out() {
+        let flow = BELL_8081.propellant_mass_per_second();
+        let stage = SimpleStage {
+            dry_mass: 1000.0,
+            engines: vec![BELL_8081.with_burn_time(100.0), BELL_8081.with_burn_time(40.0)],
+        };
+        let stage = JettisonStage::new(stage, vec![jettison(100.0, JettisonTrigger::Time(60.0))]).unwrap();
+
+        // Both engines for 40 s, one until the jettison at 60 s, then one
+        // for the last 40 s
+        assert_phases(&[
+            (40.0, 1000.0 + 140.0 * flow, 1000.0 + 60.0 * flow),
+            (20.0, 1000.0 + 60.0 * flow, 1000.0 + 40.0 * flow),
+            (40.0, 900.0 + 40.0 * flow, 900.0),
+        ], &stage.phases());
+    }
+
+    #[test]
+    fn resized_jettisons_keep_their_place_in_the_burn() {
+        let stage = JettisonStage::new(agena(), vec![jettison(200.0, JettisonTrigger::Time(40.0))]).unwrap();
+        let resized = stage.resized(2.0, 0.0);
+        assert!(close(resized.burn_time(), 80.0));
+        assert!(close(resized.next_stage().unwrap().burn_time(), 120.0));
+    }
+
+    #[test]
+    fn invalid_jettisons_are_rejected() {
+        let invalid = [
+            (jettison(10.0, JettisonTrigger::Time(0.0)), "times"),
+            (jettison(10.0, JettisonTrigger::Time(-5.0)), "times"),
+            (jettison(10.0, JettisonTrigger::PropellantFraction(0.0)), "fractions"),
+            (jettison(10.0, JettisonTrigger::PropellantFraction(1.5)), "fractions"),
+            (jettison(-10.0, JettisonTrigger::Time(5.0)), "mass"),
+            (jettison(1500.0, JettisonTrigger::Time(5.0)), "mass"),
+        ];
+        for &(jettison, message) in &invalid {
+            match JettisonStage::new(agena(), vec![jettison]) {
+                Err(e) => assert!(e.to_string().contains(message), "{:?}: {}", jettison, e),
+                Ok(_) => panic!("{:?} was accepted", jettison),
+            }
+        }
+    }
+
+    #[test]
+    fn crossfeed_drains_the_boosters_first() {
+        let flow = BELL_8081.propellant_mass_per_second();
+        let booster = |dry_mass, burn_time| SimpleStage { dry_mass, engines: vec![BELL_8081.with_burn_time(burn_time)] };
+        let stage = CrossfeedStage {
+            core: agena(),
+            boosters: vec![
+                BoosterGroup { booster: booster(200.0, 50.0), count: 2, ignition_time: 0.0 },
+                BoosterGroup { booster: booster(300.0, 80.0), count: 2, ignition_time: 0.0 },
+            ],
+        };
+
+        // Five engines drain the first pair's 2 * 50 s of propellant, three
+        // drain the second pair's 2 * 80 s, and the core is still full when
+        // they're gone
+        assert_phases(&[
+            (20.0, 2000.0 + 360.0 * flow, 2000.0 + 260.0 * flow),
+            (160.0 / 3.0, 1600.0 + 260.0 * flow, 1600.0 + 100.0 * flow),
+            (100.0, 1000.0 + 100.0 * flow, 1000.0),
+        ], &stage.phases());
+        assert_eq!(5, stage.engines().len());
+        assert_eq!(3, stage.next_stage().unwrap().engines().len());
+    }
+
+    #[test]
+    fn boiloff_strands_the_propellant_left_over() {
+        let stage = SimpleStage { dry_mass: 500.0, engines: vec![RL10A_1] };
+        let litres = |fuel: &Fuel| RL10A_1.fuel_consumption.iter().find(|f| f.0 == *fuel).unwrap().1 * 430.0;
+        let (hydrogen, oxygen) = (litres(&LIQUID_HYDROGEN), litres(&LIQUID_OXYGEN));
+
+        // Two days at 5% and 1% a day, scaled by the insulation
+        let cases = [
+            (NO_INSULATION, 0.95 * 0.95, 0.99 * 0.99),
+            (FOAM, 0.985 * 0.985, 0.997 * 0.997),
+            (MLI, 0.9975 * 0.9975, 0.9995 * 0.9995),
+        ];
+        for &(ref insulation, hydrogen_left, oxygen_left) in &cases {
+            let after = stage.after_boiloff(2.0, insulation);
+            let lost = hydrogen * (1.0 - hydrogen_left) * LIQUID_HYDROGEN.density
+                + oxygen * (1.0 - oxygen_left) * LIQUID_OXYGEN.density;
+
+            // Hydrogen runs short first, leaving some oxygen unburned
+            assert!(close(430.0 * hydrogen_left, after.burn_time()), "{}", insulation.name);
+            assert!(close(500.0 + oxygen * (oxygen_left - hydrogen_left) * LIQUID_OXYGEN.density, after.dry_mass), "{}", insulation.name);
+            assert!(close(stage.wet_mass() - lost, after.wet_mass()), "{}", insulation.name);
+        }
+    }
+
+    #[test]
+    fn storable_propellants_dont_boil_off() {
+        let after = agena().after_boiloff(30.0, &NO_INSULATION);
+        assert_eq!(masses(&[agena()]), masses(&[after]));
+    }
+}
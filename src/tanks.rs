@@ -0,0 +1,165 @@
+//! Propellant tanks.
+//!
+//! Tank volume comes from the engines' fuel consumption, which is in litres
+//! per second, and tank mass from the tank type's mass per litre. Masses per
+//! litre are approximations of the Real Fuels tank types.
+
+use engines::Engine;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TankType {
+    pub name: &'static str,
+    /// Dry mass per litre of capacity, in kg
+    pub mass_per_litre: f64,
+}
+
+/// Pressure stabilized tanks, like the Atlas
+pub const BALLOON: TankType = TankType {
+    name: "Balloon",
+    mass_per_litre: 0.05,
+};
+
+/// Conventional self supporting tanks
+pub const STAINLESS: TankType = TankType {
+    name: "Stainless",
+    mass_per_litre: 0.1,
+};
+
+/// Tanks integrated with service module structure and pressurant
+pub const SERVICE_MODULE: TankType = TankType {
+    name: "ServiceModule",
+    mass_per_litre: 0.16,
+};
+
+pub const ALL: &[TankType] = &[BALLOON, STAINLESS, SERVICE_MODULE];
+
+impl TankType {
+    pub fn find(name: &str) -> Option<TankType> {
+        ALL.iter().find(|t| t.name.eq_ignore_ascii_case(name)).cloned()
+    }
+
+    /// Mass of tanks holding enough propellant for every engine's full burn
+    pub fn mass_for(&self, engines: &[Engine]) -> f64 {
+        volume(engines) * self.mass_per_litre
+    }
+}
+
+/// Litres of propellant needed for every engine's full burn
+pub fn volume(engines: &[Engine]) -> f64 {
+    engines.iter()
+        .flat_map(|e| e.fuel_consumption.iter().map(move |&(_, rate)| rate * e.burn_time))
+        .sum()
+}
+
+/// Tank insulation, which slows the boiloff of cryogenic propellants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Insulation {
+    pub name: &'static str,
+    /// Multiplies each fuel's `boiloff_rate`
+    pub boiloff_factor: f64,
+}
+
+pub const NO_INSULATION: Insulation = Insulation {
+    name: "None",
+    boiloff_factor: 1.0,
+};
+
+/// Spray-on foam, as on the Centaur and S-IVB
+pub const FOAM: Insulation = Insulation {
+    name: "Foam",
+    boiloff_factor: 0.3,
+};
+
+/// Multi-layer insulation blankets, for long coasts
+pub const MLI: Insulation = Insulation {
+    name: "MLI",
+    boiloff_factor: 0.05,
+};
+
+pub const INSULATION: &[Insulation] = &[NO_INSULATION, FOAM, MLI];
+
+impl Insulation {
+    pub fn find(name: &str) -> Option<Insulation> {
+        INSULATION.iter().find(|i| i.name.eq_ignore_ascii_case(name)).cloned()
+    }
+}
+
+/// How tank mass scales with propellant, for sizing stages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TankModel {
+    /// Tank dry mass per kg of propellant
+    MassFraction(f64),
+    /// Tank dry mass from the volume of the propellant, which depends on the
+    /// mix the engines burn
+    Type(TankType),
+}
+
+impl Default for TankModel {
+    fn default() -> Self {
+        TankModel::MassFraction(0.1)
+    }
+}
+
+impl TankModel {
+    /// Tank dry mass per kg of propellant burned by `engines`
+    pub fn mass_fraction(&self, engines: &[Engine]) -> f64 {
+        match *self {
+            TankModel::MassFraction(fraction) => fraction,
+            TankModel::Type(tank_type) => {
+                let mass_flow = engines.iter().map(Engine::propellant_mass_per_second).sum::<f64>();
+                let volume_flow = engines.iter()
+                    .flat_map(|e| e.fuel_consumption.iter().map(|&(_, rate)| rate))
+                    .sum::<f64>();
+                if mass_flow > 0.0 {
+                    tank_type.mass_per_litre * volume_flow / mass_flow
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Mass of tanks holding enough propellant for every engine's full burn
+    pub fn mass_for(&self, engines: &[Engine]) -> f64 {
+        match *self {
+            TankModel::MassFraction(fraction) => {
+                fraction * engines.iter().map(Engine::propellant_mass_for_full_burn).sum::<f64>()
+            }
+            TankModel::Type(tank_type) => tank_type.mass_for(engines),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engines::BELL_8081;
+
+    #[test]
+    fn tank_types_scale_with_volume() {
+        // 8.9903 + 11.0327 l/s for 240 s
+        let litres = 20.023 * 240.0;
+        let propellant = BELL_8081.propellant_mass_for_full_burn();
+        assert!((volume(&[BELL_8081]) - litres).abs() < 1e-9);
+        for &(tank_type, mass_per_litre) in &[(BALLOON, 0.05), (STAINLESS, 0.1), (SERVICE_MODULE, 0.16)] {
+            let model = TankModel::Type(tank_type);
+            assert!((model.mass_for(&[BELL_8081]) - litres * mass_per_litre).abs() < 1e-9, "{}", tank_type.name);
+            assert!((model.mass_fraction(&[BELL_8081]) * propellant - litres * mass_per_litre).abs() < 1e-9, "{}", tank_type.name);
+        }
+    }
+
+    #[test]
+    fn mass_fractions_scale_with_propellant_mass() {
+        let propellant = BELL_8081.propellant_mass_for_full_burn();
+        assert_eq!(0.1, TankModel::default().mass_fraction(&[BELL_8081]));
+        assert!((TankModel::MassFraction(0.08).mass_for(&[BELL_8081, BELL_8081]) - 0.08 * 2.0 * propellant).abs() < 1e-9);
+        assert_eq!(0.0, TankModel::Type(STAINLESS).mass_fraction(&[]));
+    }
+
+    #[test]
+    fn names_ignore_case() {
+        assert_eq!(Some(SERVICE_MODULE), TankType::find("servicemodule"));
+        assert_eq!(Some(MLI), Insulation::find("mli"));
+        assert_eq!(None, TankType::find("Aluminium"));
+    }
+}
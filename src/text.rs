@@ -0,0 +1,299 @@
+//! Human readable output for the command line.
+
+use std::fmt;
+
+use ansi_term::Colour::{Blue, Red, Yellow};
+
+use ascent::AscentResult;
+use bodies::{Body, System};
+use catalog::Catalog;
+use design::{EngineRef, RocketDesign};
+use destinations::{destinations, payload_targets, DeltaVMap, Route, SAFETY_MARGIN};
+use dispersion::Samples;
+use engines::{Engine, SEA_LEVEL};
+use error::Error;
+use finite_burn::{finite_burn, perigee_kicks, StartOrbit, MAX_KICKS};
+use mission::{BurnReport, MissionPlan};
+use rocket::{Limit, PayloadTarget, Rocket};
+use search::SearchResult;
+use stage::Stage;
+
+pub fn print_stages(rocket: &Rocket) {
+    println!("{:5}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}", "stage", "delta-v", "wet mass", "dry mass", "Start TWR", "End TWR", "burn time");
+    let reversed_stages = rocket.stage_numbers().into_iter().zip(rocket.stages()).enumerate().collect::<Vec<_>>().into_iter().rev();
+    for (i, (number, stage)) in reversed_stages {
+        // The first stage lights on the pad
+        let start_twr = if i == 0 { stage.twr() } else { stage.vacuum_twr() };
+        println!("{:>5}: {:6.0} m/s  {:10.0}  {:10.0}  {:>10.2}  {:>10.2}  {:>10}", number.to_string(), stage.delta_v(), stage.wet_mass(), stage.dry_mass(), start_twr, stage.max_g_force(), BurnTime(stage.burn_time()));
+    }
+    println!("{}", "-".repeat(78));
+    println!("Total: {:6.0} m/s", rocket.delta_v());
+    println!("Max G: {:10.2}", rocket.max_g_force());
+    println!("Lift-off TWR: {:.2}", rocket.liftoff_twr());
+    let stage_reliabilities = rocket.stages.iter()
+        .map(|s| format!("{:.1}%", s.reliability() * 100.0))
+        .collect::<Vec<_>>();
+    println!("Success chance: {:.1}% (stages: {})", rocket.reliability() * 100.0, stage_reliabilities.join(", "));
+}
+
+pub fn print_component_penalty(design: &RocketDesign, catalog: &Catalog, system: &System) -> Result<(), Error> {
+    let target = PayloadTarget { delta_v: system.launch_delta_v, min_liftoff_twr: 1.0, ..PayloadTarget::default() };
+    if let Some(penalty) = design.component_penalty(catalog, system.home, &target)? {
+        let effect = if penalty >= 0.0 { "cost" } else { "save" };
+        println!("Fairing and escape system {} {:.0} kg of payload to orbit compared with dropping them with the first stage", effect, penalty.abs());
+    }
+    Ok(())
+}
+
+pub fn print_g_limited_burns(rocket: &Rocket, max_g_force: f64) {
+    println!("Throttled to {} g:", max_g_force);
+    let reversed_stages = rocket.stage_numbers().into_iter().zip(rocket.stages()).collect::<Vec<_>>().into_iter().rev();
+    for (number, stage) in reversed_stages {
+        let burn = stage.g_limited_burn(max_g_force);
+        let line = format!("{:>5}: {:>10}  {:10.2} g", number.to_string(), BurnTime(burn.burn_time), burn.max_g_force);
+        if burn.achievable {
+            println!("{}", line);
+        } else {
+            println!("{}", Red.paint(format!("{}  (can't throttle down far enough)", line)));
+        }
+    }
+}
+
+pub fn print_dispersion(samples: &Samples, system: &System, target_name: &str) {
+    println!("{:20}  {:>10}  {:>10}  {:>10}", "", "5%", "50%", "95%");
+    let percentiles = |values: &[f64]| {
+        [5.0, 50.0, 95.0].iter().map(|&p| Samples::percentile(values, p)).collect::<Vec<_>>()
+    };
+    let dv = percentiles(&samples.delta_v);
+    println!("{:20}  {:6.0} m/s  {:6.0} m/s  {:6.0} m/s", "Delta-v", dv[0], dv[1], dv[2]);
+    let payload = percentiles(&samples.payload);
+    println!("{:20}  {:7.0} kg  {:7.0} kg  {:7.0} kg", format!("Payload to {}", target_name), payload[0], payload[1], payload[2]);
+    println!();
+
+    for destination in destinations(system) {
+        let probability = samples.probability_of_delta_v(destination.delta_v);
+        if probability > 0.0 {
+            let line = format!("{:5.1}% chance of reaching {}", probability * 100.0, destination.name);
+            if probability < 0.5 {
+                println!("{}", Red.paint(line));
+            } else if probability < 0.95 {
+                println!("{}", Yellow.paint(line));
+            } else {
+                println!("{}", Blue.paint(line));
+            }
+        }
+    }
+}
+
+pub fn print_burns(plan: &MissionPlan, reports: &[BurnReport]) {
+    println!("{:4}  {:20}  {:5}  {:>10}  {:>10}  {:>10}  {:>10}", "burn", "name", "stage", "delta-v", "propellant", "margin", "ignitions");
+    for report in reports {
+        let name = plan.burns[report.burn].name.as_deref().unwrap_or("");
+        let ignitions = match report.ignitions_left {
+            Some(left) => format!("{} ({} left)", report.ignitions_used, left),
+            None => report.ignitions_used.to_string(),
+        };
+        let line = format!("{:4}  {:20}  {:5}  {:6.0} m/s  {:7.0} kg  {:6.0} m/s  {:>10}", report.burn + 1, name, report.stage, report.delta_v, report.remaining_propellant, report.delta_v_margin, ignitions);
+        if !report.complete {
+            println!("{}", Red.paint(format!("{}  (ran dry)", line)));
+        } else if report.delta_v_margin < 0.0 {
+            println!("{}", Yellow.paint(line));
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+pub fn print_ascent(result: &AscentResult) {
+    if result.reached_orbit {
+        println!("Reached a {:.0} x {:.0} km orbit", result.periapsis / 1000.0, result.apoapsis / 1000.0);
+    } else {
+        println!("{}", Red.bold().paint("This rocket will not reach orbit"));
+        println!("Final orbit: {:.0} x {:.0} km", result.periapsis / 1000.0, result.apoapsis / 1000.0);
+    }
+    println!("Time:             {:>10}", BurnTime(result.time));
+    println!("Stages used:      {:>10}", result.stages_used);
+    println!("Orbital speed:    {:6.0} m/s", result.orbital_speed);
+    println!("Delta-v expended: {:6.0} m/s", result.delta_v_expended);
+    println!("Gravity losses:   {:6.0} m/s", result.gravity_loss);
+    println!("Drag losses:      {:6.0} m/s", result.drag_loss);
+    println!("Steering losses:  {:6.0} m/s", result.steering_loss);
+    println!("Max Q:            {:6.1} kPa", result.max_dynamic_pressure / 1000.0);
+    println!("Max G:            {:8.2}", result.max_g_force);
+}
+
+pub fn print_finite_burn(stage: &dyn Stage, body: &Body, orbit: StartOrbit, delta_v: f64, max_loss: f64) {
+    let burn = match finite_burn(stage, body, orbit, delta_v) {
+        Some(burn) => burn,
+        None => {
+            println!("{}", Red.bold().paint(format!("This stage can't make a {:.0} m/s burn", delta_v)));
+            return;
+        }
+    };
+    println!("Burn time:  {:>10}", BurnTime(burn.burn_time));
+    println!("Delta-v:    {:6.0} m/s", burn.delta_v);
+    let line = format!("Loss:       {:6.0} m/s ({:.1}%)", burn.loss, burn.loss / delta_v * 100.0);
+    if burn.loss <= max_loss * delta_v {
+        println!("{}", line);
+        return;
+    }
+    println!("{}", Yellow.paint(line));
+
+    println!();
+    match perigee_kicks(stage, body, orbit, delta_v, max_loss) {
+        Some(kicks) => {
+            let loss = kicks.iter().map(|k| k.loss).sum::<f64>();
+            println!("Split into {} perigee kicks to lose {:.0} m/s ({:.1}%):", kicks.len(), loss, loss / delta_v * 100.0);
+            println!("{:4}  {:>10}  {:>10}  {:>10}", "kick", "delta-v", "burn time", "loss");
+            for (i, kick) in kicks.iter().enumerate() {
+                println!("{:4}  {:6.0} m/s  {:>10}  {:6.0} m/s", i + 1, kick.delta_v, BurnTime(kick.burn_time), kick.loss);
+            }
+        }
+        None => println!("{}", Red.paint(format!("No split into up to {} perigee kicks keeps the loss under {:.1}%", MAX_KICKS, max_loss * 100.0))),
+    }
+}
+
+pub fn print_engines(catalog: &Catalog) {
+    println!("{:40}  {:>8}  {:>8}  {:>10}  {:>8}  {:>10}", "engine", "isp", "SL isp", "thrust", "mass", "burn time");
+    for engine in catalog.engines() {
+        println!("{:40}  {:6.1} s  {:6.1} s  {:7.1} kN  {:5.0} kg  {:>10}", engine.name, engine.isp, engine.sea_level_isp, engine.thrust, engine.mass, BurnTime(engine.burn_time));
+    }
+}
+
+pub fn print_engine(engine: &Engine) {
+    println!("{}", engine.name);
+    println!("  Isp:        {:.1} s (vacuum), {:.1} s (sea level)", engine.isp, engine.sea_level_isp);
+    println!("  Thrust:     {:.1} kN (vacuum), {:.1} kN (sea level)", engine.thrust, engine.thrust_at(SEA_LEVEL));
+    println!("  Mass:       {:.0} kg", engine.mass);
+    println!("  Burn time:  {}", BurnTime(engine.burn_time));
+    match engine.ignitions {
+        Some(ignitions) => println!("  Ignitions:  {}", ignitions),
+        None => println!("  Ignitions:  unlimited"),
+    }
+    println!("  Ullage:     {}", if engine.ullage { "required" } else { "not needed" });
+    println!("  Mass flow:  {:.2} kg/s", engine.propellant_mass_per_second());
+    println!("  Propellants for a full burn:");
+    for (fuel, amount) in engine.propellants_required() {
+        println!("    {:20} {:10.0} L  {:10.0} kg", fuel.name, amount, amount * fuel.density);
+    }
+}
+
+pub fn print_fuels(catalog: &Catalog) {
+    println!("{:20}  {:>12}", "fuel", "density");
+    for fuel in catalog.fuels() {
+        println!("{:20}  {:7.4} kg/L", fuel.name, fuel.density);
+    }
+}
+
+/// A duration in s, shown in minutes and seconds
+pub struct BurnTime(pub f64);
+
+impl fmt::Display for BurnTime {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = self.0 as u64 / 60;
+        let seconds = self.0 as u64 % 60;
+        if seconds == 0 {
+            format!("{}m", minutes).fmt(fmt)
+        } else if minutes == 0 {
+            format!("{}s", seconds).fmt(fmt)
+        } else {
+            format!("{}m {}s", minutes, seconds).fmt(fmt)
+        }
+    }
+}
+
+pub fn print_where_rocket_can_go(rocket: &Rocket, system: &System) {
+    let dv = rocket.delta_v();
+    if dv <= system.launch_delta_v {
+        println!("{}", Red.bold().paint("This rocket will not reach orbit"));
+    }
+
+    for destination in destinations(system) {
+        print_if_rocket_can_go_to(dv, destination.delta_v, &destination.name);
+    }
+    if dv > system.launch_delta_v {
+        println!("Note: Assumes no gravity assists");
+    }
+}
+
+pub fn print_if_rocket_can_go_to(dv: f64, required_dv: f64, name: &str) {
+    if dv > required_dv * SAFETY_MARGIN {
+        let excess = dv - required_dv;
+        println!("{}", Blue.paint(format!("This rocket can go to {} with {:.0} m/s excess dV", name, excess)));
+    } else if dv > required_dv {
+        println!("{}", Yellow.paint(format!("This rocket can go to {} without safety margins", name)));
+    }
+}
+
+pub fn print_search_results(results: &[SearchResult]) {
+    if results.is_empty() {
+        println!("{}", Red.bold().paint("No designs meet these requirements"));
+    }
+    for (i, result) in results.iter().enumerate() {
+        println!("{}. {:.0} kg, {:.0} m/s, cost {:.0}, payload fraction {:.2}%", i + 1, result.liftoff_mass, result.delta_v, result.cost, result.payload_fraction * 100.0);
+        for (j, stage) in result.design.stages.iter().enumerate() {
+            print!("   stage {}: {}", j + 1, describe_engines(&stage.engines));
+            if let Some(ref vernier) = stage.verniers {
+                print!(" + 2x {}", vernier);
+            }
+            for boosters in &stage.boosters {
+                print!(" with {} boosters of {}", boosters.count, describe_engines(&boosters.engines));
+            }
+            println!();
+        }
+    }
+}
+
+fn describe_engines(engines: &[EngineRef]) -> String {
+    engines.iter()
+        .map(|e| format!("{}x {}", e.count, e.name))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+pub fn print_route(route: &Route) {
+    for (i, delta_v) in route.legs.iter().enumerate() {
+        println!("{:>25} -> {:25}  {:6.0} m/s", route.locations[i], route.locations[i + 1], delta_v);
+    }
+    println!("{}", "-".repeat(65));
+    println!("Total: {:6.0} m/s", route.delta_v());
+}
+
+pub fn print_map(map: &DeltaVMap) {
+    for edge in map.edges() {
+        let aerobrake = if edge.aerobrake { "  (aerobrake)" } else { "" };
+        println!("{:>25} -> {:25}  {:6.0} m/s{}", map.locations()[edge.from], map.locations()[edge.to], edge.delta_v, aerobrake);
+    }
+}
+
+pub fn print_max_payloads(rocket: &Rocket, system: &System, target: PayloadTarget) {
+    for (name, required_dv) in payload_targets(system) {
+        print_max_payload(rocket, PayloadTarget { delta_v: required_dv, ..target }, &name);
+    }
+}
+
+pub fn print_max_payload(rocket: &Rocket, target: PayloadTarget, name: &str) {
+    if let Some(max) = rocket.max_payload(&target) {
+        let limit = match max.limit {
+            Some(Limit::DeltaV) => "delta-v".to_string(),
+            Some(Limit::LiftoffTwr) => "lift-off TWR".to_string(),
+            Some(Limit::StageTwr(number)) => format!("stage {} TWR", number),
+            None => "unlimited".to_string(),
+        };
+        println!("Max to {}: {:.0} kg (limited by {})", name, max.payload_mass, limit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burn_times_show_minutes_and_seconds() {
+        let shown = [0.0, 45.9, 120.0, 150.5, 3725.0].iter()
+            .map(|&t| BurnTime(t).to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["0m", "45s", "2m", "2m 30s", "62m 5s"], shown);
+        assert_eq!("    2m 30s", format!("{:>10}", BurnTime(150.0)));
+    }
+}